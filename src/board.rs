@@ -3,6 +3,7 @@ use std::default::Default;
 // use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 
 use crate::{
+    movement::castling::CastlingRights,
     // This is just to call Report::col_row_to_index later.
     movement::report::Report,
     pieces::{
@@ -18,6 +19,9 @@ use crate::{
 pub struct Board{
     squares: [u8; 64],
     built_squares: [Piece; 64],
+    castling: CastlingRights,
+    // Index of the position a pawn skipped with a two-step move in the last turn.
+    en_passant: Option<u8>,
     // turn: Player,
 }
 
@@ -61,6 +65,8 @@ impl Default for Board{
         Board{
             squares,
             built_squares,
+            castling: CastlingRights::default(),
+            en_passant: None,
         }
     }
 }
@@ -123,9 +129,128 @@ impl Board{
         Board{
             squares,
             built_squares,
+            castling: CastlingRights::default(),
+            en_passant: None,
         }
     }
 
+    pub fn get_castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    pub fn set_castling_rights(&mut self, castling: CastlingRights) {
+        self.castling = castling;
+    }
+
+    pub fn get_en_passant(&self) -> Option<u8> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: Option<u8>) {
+        self.en_passant = en_passant;
+    }
+
+    /// True if a pawn of the player to move stands beside the pawn that took two steps.
+    /// Only then the en passant position makes a difference when comparing positions.
+    fn is_en_passant_possible(&self) -> bool {
+        let index: u8 = match self.en_passant {
+            None => return false,
+            Some(index) => index,
+        };
+
+        let col: i8 = (index % 8) as i8;
+        // A white pawn skipped row 5, so black captures from row 4. And the other way around.
+        let (row, pawn_number): (u8, u8) = match index / 8 {
+            2 => (3, 1),
+            _ => (4, 7),
+        };
+
+        for side in [col - 1, col + 1] {
+            if (0..8).contains(&side) && (self.squares[Report::col_row_to_index(side as u8, row) as usize] == pawn_number) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Everything that makes two positions the same for repetition rules:
+    /// all pieces, the player to move, castling rights and en passant.
+    pub fn get_position_key(&self, player_turn: bool) -> Vec<u8> {
+        let mut key: Vec<u8> = Vec::from(self.squares);
+
+        key.push(player_turn as u8);
+        key.push(self.castling.get_key());
+
+        // 64 means no en passant available.
+        match (self.is_en_passant_possible(), self.en_passant) {
+            (true, Some(index)) => key.push(index),
+            (_, _) => key.push(64),
+        }
+
+        key
+    }
+
+    /// True if neither player has enough pieces left to checkmate: K vs K, K+B vs K or K+N vs K.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut remaining: Vec<u8> = Vec::new();
+
+        for piece_number in self.squares.iter() {
+            match piece_number {
+                // Empty squares and kings.
+                0 | 6 | 12 => {},
+                _ => remaining.push(*piece_number),
+            }
+        }
+
+        match remaining[..] {
+            [] => true,
+            // A single knight or bishop, of either color.
+            [3] | [4] | [9] | [10] => true,
+            _ => false,
+        }
+    }
+
+    /// True if the player of color can't checkmate whatever the rival does: only the king is left,
+    /// or the king and a single knight or bishop against a lone king. Used when the rival runs out of time.
+    pub fn is_insufficient_material_for(&self, color: &Color) -> bool {
+        let rival_color: Color = match color {
+            Color::WHITE => Color::BLACK,
+            Color::BLACK => Color::WHITE,
+            Color::EMPTY => panic!("Error in Board.is_insufficient_material_for. Color is Empty."),
+        };
+
+        // With any other rival piece or pawn, a knight or bishop can still mate a king that gets blocked in.
+        match self.get_pieces_besides_king(color)[..] {
+            [] => true,
+            [3] | [4] => self.get_pieces_besides_king(&rival_color).is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Piece numbers of the player of color other than the king, as the white ones (1 pawn to 5 queen).
+    fn get_pieces_besides_king(&self, color: &Color) -> Vec<u8> {
+        let (first, last): (u8, u8) = match color {
+            Color::WHITE => (1, 6),
+            Color::BLACK => (7, 12),
+            Color::EMPTY => panic!("Error in Board.get_pieces_besides_king. Color is Empty."),
+        };
+
+        self.squares
+            .iter()
+            .filter(|piece_number| (**piece_number >= first) && (**piece_number < last))
+            .map(|piece_number| *piece_number - first + 1)
+            .collect()
+    }
+
+    /// Clear the castling rights tied to the square in given index.
+    /// Used by step when a move is finalized.
+    pub fn remove_castling_rights(&mut self, index: u8) {
+        self.castling.remove_rights_at(
+            index,                      // index: u8,
+        );
+    }
+
     pub fn get_board_array(&self) -> [u8; 64] {
         return self.squares.clone();
     }
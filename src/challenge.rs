@@ -0,0 +1,147 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U64, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+use crate::game::clock::TimeControl;
+
+
+/// Id of a challenge in the Chess contract. A string in JSON, so clients don't lose precision.
+pub type ChallengeId = U64;
+
+
+/// Color the challenger wants to play.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ColorPreference{
+    White,
+    Black,
+    // Picked when the challenge is accepted.
+    Random,
+}
+
+/// An invitation to play, waiting for someone to accept it.
+#[derive(Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Challenge{
+    id: ChallengeId,
+    challenger: AccountId,
+    // If set, only this account can accept it.
+    opponent: Option<AccountId>,
+    color: ColorPreference,
+    // None for a game without clock.
+    time_control: Option<TimeControl>,
+    // If the game changes the ratings of the players.
+    rated: bool,
+    // What the challenger puts in, in yoctoNEAR or the smallest unit of token. Whoever accepts must put the same.
+    wager: U128,
+    // NEP-141 contract of the wager, None for NEAR.
+    token: Option<AccountId>,
+    // A token wager is only put in after the challenge is created, with ft_transfer_call.
+    funded: bool,
+    created_at: U64,
+}
+
+impl Challenge{
+    /// An unrated challenge without wager. See set_rated and set_wager.
+    pub fn new(
+        id: ChallengeId,
+        challenger: AccountId,
+        opponent: Option<AccountId>,
+        color: ColorPreference,
+        time_control: Option<TimeControl>,
+        created_at: u64,
+    ) -> Self {
+        Challenge{
+            id,
+            challenger,
+            opponent,
+            color,
+            time_control,
+            rated: false,
+            wager: U128(0),
+            token: None,
+            funded: true,
+            created_at: U64(created_at),
+        }
+    }
+
+    pub fn set_rated(&mut self, rated: bool) {
+        self.rated = rated;
+    }
+
+    /// Each player puts wager of token (None for NEAR). A token wager isn't funded until the challenger sends it.
+    pub fn set_wager(&mut self, wager: u128, token: Option<AccountId>) {
+        self.wager = U128(wager);
+        self.funded = token.is_none();
+        self.token = token;
+    }
+
+    pub fn get_id(&self) -> ChallengeId {
+        self.id
+    }
+
+    pub fn get_challenger(&self) -> AccountId {
+        self.challenger.clone()
+    }
+
+    pub fn get_opponent(&self) -> Option<AccountId> {
+        self.opponent.clone()
+    }
+
+    pub fn get_color(&self) -> ColorPreference {
+        self.color
+    }
+
+    pub fn get_time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    pub fn is_rated(&self) -> bool {
+        self.rated
+    }
+
+    pub fn get_wager(&self) -> U128 {
+        self.wager
+    }
+
+    pub fn get_token(&self) -> Option<AccountId> {
+        self.token.clone()
+    }
+
+    pub fn is_funded(&self) -> bool {
+        self.funded
+    }
+
+    /// The challenger put the token wager in.
+    pub fn fund(&mut self) {
+        self.funded = true;
+    }
+
+    pub fn get_created_at(&self) -> U64 {
+        self.created_at
+    }
+
+    /// True if account_id is allowed to accept it. Never before the challenger's wager is in.
+    pub fn can_accept(&self, account_id: &AccountId) -> bool {
+        match &self.opponent {
+            _ if !self.funded || (*account_id == self.challenger) => false,
+            None => true,
+            Some(opponent) => opponent == account_id,
+        }
+    }
+
+    /// (white, black) once accepted by account_id. random_byte decides a Random color.
+    pub fn get_players(&self, account_id: &AccountId, random_byte: u8) -> (AccountId, AccountId) {
+        let challenger_white: bool = match self.color {
+            ColorPreference::White => true,
+            ColorPreference::Black => false,
+            ColorPreference::Random => (random_byte & 1) == 0,
+        };
+
+        match challenger_white {
+            true => (self.challenger.clone(), account_id.clone()),
+            false => (account_id.clone(), self.challenger.clone()),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json;
+use near_sdk::{env, AccountId};
+
+use crate::{
+    challenge::{
+        Challenge,
+        ChallengeId,
+    },
+    game::{
+        clock::TimeControl,
+        history::MoveRecord,
+        result::GameResult,
+        Game,
+        GameId,
+    },
+    notation::uci,
+    rating::Rating,
+};
+
+
+// Events follow NEP-297, so indexers can follow the games without reading the contract state:
+// EVENT_JSON:{"standard":"chess","version":"1.0.0","event":"move_made","data":[{...}]}
+const STANDARD: &str = "chess";
+const VERSION: &str = "1.0.0";
+
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T: Serialize> {
+    standard: &'a str,
+    version: &'a str,
+    event: &'a str,
+    data: Vec<T>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct GameData {
+    game_id: GameId,
+    white: AccountId,
+    black: AccountId,
+    // Position the game started from, the usual one if None.
+    start_fen: Option<String>,
+    // None for a game without clock.
+    time_control: Option<TimeControl>,
+    // None if it's not a correspondence game.
+    days_per_move: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct MoveData {
+    game_id: GameId,
+    player: AccountId,
+    san: String,
+    uci: String,
+    // Position after the move.
+    fen: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct CheckData {
+    game_id: GameId,
+    // The account under check.
+    player: AccountId,
+    checkmate: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct PlayerData {
+    game_id: GameId,
+    player: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ResultData {
+    game_id: GameId,
+    result: GameResult,
+    description: String,
+    // None for a draw.
+    winner: Option<AccountId>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct RatingData {
+    player: AccountId,
+    rating: u32,
+    // Rated games finished, including this one.
+    games: u32,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct RatingsData {
+    game_id: GameId,
+    white: RatingData,
+    black: RatingData,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ChallengeData {
+    challenge_id: ChallengeId,
+    player: AccountId,
+    // Only for an accepted challenge.
+    game_id: Option<GameId>,
+}
+
+fn log_event<T: Serialize>(event: &str, data: T) {
+    let event_log: EventLog<T> = EventLog{
+        standard: STANDARD,
+        version: VERSION,
+        event,
+        data: vec![data],
+    };
+
+    match serde_json::to_string(&event_log) {
+        // env::log instead of the crate log, so tests can read the events too.
+        Result::Ok(json) => env::log(format!("EVENT_JSON:{}", json).as_bytes()),
+        Result::Err(err) => panic!("Error in events.log_event. Couldn't serialize {} ({}).", event, err),
+    }
+}
+
+fn get_game_data(game_id: GameId, game: &Game) -> GameData {
+    GameData{
+        game_id,
+        white: game.get_white_player(),
+        black: game.get_black_player(),
+        start_fen: game.get_start_fen(),
+        time_control: game.get_time_control(),
+        days_per_move: game.get_correspondence_game().map(|correspondence| correspondence.get_days_per_move()),
+    }
+}
+
+/// A new game was stored.
+pub fn log_game_created(game_id: GameId, game: &Game) {
+    log_event("game_created", get_game_data(game_id, game));
+}
+
+/// An existing game started over, maybe with other players.
+pub fn log_game_reset(game_id: GameId, game: &Game) {
+    log_event("game_reset", get_game_data(game_id, game));
+}
+
+/// player did the move in record. game is the state after it.
+pub fn log_move_made(game_id: GameId, game: &Game, player: &AccountId, record: &MoveRecord) {
+    log_event("move_made", MoveData{
+        game_id,
+        player: player.clone(),
+        san: record.get_san(),
+        uci: uci::get_uci(record.get_from(), record.get_to(), &record.get_promotion()),
+        fen: game.get_fen_game(),
+    });
+}
+
+/// The move in record left the player to move in game under check.
+pub fn log_check(game_id: GameId, game: &Game, record: &MoveRecord) {
+    let player: AccountId = match game.get_player_turn() {
+        false => game.get_white_player(),
+        true => game.get_black_player(),
+    };
+
+    log_event("check", CheckData{
+        game_id,
+        player,
+        checkmate: record.is_checkmate(),
+    });
+}
+
+/// player offered a draw to the rival.
+pub fn log_draw_offered(game_id: GameId, player: &AccountId) {
+    log_event("draw_offered", PlayerData{
+        game_id,
+        player: player.clone(),
+    });
+}
+
+/// player declined the draw offered by the rival.
+pub fn log_draw_declined(game_id: GameId, player: &AccountId) {
+    log_event("draw_declined", PlayerData{
+        game_id,
+        player: player.clone(),
+    });
+}
+
+pub fn log_game_ended(game_id: GameId, game: &Game) {
+    let result: GameResult = game.get_result();
+    let winner: Option<AccountId> = match result {
+        GameResult::WhiteWins(_) => Some(game.get_white_player()),
+        GameResult::BlackWins(_) => Some(game.get_black_player()),
+        _ => None,
+    };
+
+    log_event("game_ended", ResultData{
+        game_id,
+        result,
+        description: result.get_description(),
+        winner,
+    });
+}
+
+/// The rated game_id ended, and its players have the new ratings.
+pub fn log_ratings_updated(game_id: GameId, white: &AccountId, white_rating: &Rating, black: &AccountId, black_rating: &Rating) {
+    log_event("ratings_updated", RatingsData{
+        game_id,
+        white: RatingData{
+            player: white.clone(),
+            rating: white_rating.get_rating(),
+            games: white_rating.get_games(),
+        },
+        black: RatingData{
+            player: black.clone(),
+            rating: black_rating.get_rating(),
+            games: black_rating.get_games(),
+        },
+    });
+}
+
+/// A new challenge is open, with everything needed to accept it.
+pub fn log_challenge_created(challenge: &Challenge) {
+    log_event("challenge_created", challenge);
+}
+
+/// player accepted the challenge, starting game_id.
+pub fn log_challenge_accepted(challenge_id: ChallengeId, player: &AccountId, game_id: GameId) {
+    log_event("challenge_accepted", ChallengeData{
+        challenge_id,
+        player: player.clone(),
+        game_id: Some(game_id),
+    });
+}
+
+/// The challenger took the challenge back.
+pub fn log_challenge_cancelled(challenge_id: ChallengeId, player: &AccountId) {
+    log_event("challenge_cancelled", ChallengeData{
+        challenge_id,
+        player: player.clone(),
+        game_id: None,
+    });
+}
@@ -1,29 +1,90 @@
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, near_bindgen};
+use near_sdk::{env, near_bindgen, AccountId};
+use near_sdk::json_types::{U64, U128};
 
 use near_sdk::serde::{Deserialize, Serialize};
 
+pub mod clock;
+pub mod correspondence;
+pub mod history;
+pub mod legal_moves;
+pub mod outcome;
+pub mod result;
+
+/// Id of a game in the Chess contract. A string in JSON, so clients don't lose precision.
+pub type GameId = U64;
+
 use crate::{
     log,
     board::Board,
-    movement::step::Step,
+    game::{
+        clock::{
+            Clock,
+            TimeControl,
+        },
+        correspondence::Correspondence,
+        history::MoveRecord,
+        legal_moves::LegalMove,
+        outcome::MoveOutcome,
+        result::{
+            DrawReason,
+            GameResult,
+            WinReason,
+        },
+    },
+    movement::{
+        castling::CastlingRights,
+        report::Report,
+        step::Step,
+    },
+    notation::{
+        fen::{
+            self,
+            FenError,
+            FenPosition,
+        },
+        pgn,
+        san,
+        uci,
+    },
     player::{
         errors::ErrorResponse,
+        player::Player,
         players::Players,
         turn::Turn,
     },
-    pieces::piece::Piece,
+    pieces::{
+        piece::{
+            Color,
+            Piece,
+        },
+        piece_ext::PieceExt,
+        promotion::Promotion,
+    },
 };
 
 
 // squares represent each position in the board.
-// turn is how many movements the game has completed.
+// turn is how many movements were played in this game. The game is a draw at 255.
+// start_turn: how many movements were done before start_fen, from its fullmove number. Only used for notation.
 // player_turn: false: White, true: Black
-// game_over: if game is still running. false means no moves can be done.
-//
-// Later will implement a list of all steps taken.
-// That way we can revert to a previous turn, through player's request.
+// result: Running while the game goes on. Otherwise, who won (or draw) and why. No moves can be done after it.
+// castling: which castling moves each player can still do.
+// en_passant: index of the position a pawn skipped in the last turn, if any.
+// halfmove_clock: turns since the last capture or pawn move. A draw can be claimed at 100 and is forced at 150.
+// white, black: accounts playing each color. Set by create_game and reset_game.
+// positions: key of each position since the last capture or pawn move (Board.get_position_key), for repetition rules.
+// history: every move done since the start of the game. Read it with get_history.
+// created_at: block timestamp (nanoseconds) when the game was created or reset.
+// start_fen: the position the game started from, if it wasn't the usual one.
+// draw_offer: account of the player offering a draw, until the rival answers or the player moves again.
+// clock: remaining time of each player, if the game has a time control.
+// correspondence: deadline for the next move and vacation days left, if it's a correspondence game.
+// wager: what each player put in to play, in yoctoNEAR or the smallest unit of token. The game can't be reset if it's not 0.
+// token: NEP-141 contract of the wager, None for NEAR.
+// pot: what the contract holds for the game. Paid out once the game ends, 0 after that.
+// rated: if the result changes the ratings of the players. Only games from a rated challenge are.
 
 #[near_bindgen]
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
@@ -32,25 +93,69 @@ pub struct Game{
     squares: Vec<u8>,
 
     turn: u8,
+    start_turn: u32,
     player_turn: bool,
-    game_over: bool,
+    result: GameResult,
 
     black_check: bool,
     white_check: bool,
+
+    castling: CastlingRights,
+    en_passant: Option<u8>,
+    halfmove_clock: u8,
+
+    white: AccountId,
+    black: AccountId,
+
+    created_at: U64,
+    start_fen: Option<String>,
+    draw_offer: Option<AccountId>,
+    clock: Option<Clock>,
+    correspondence: Option<Correspondence>,
+    wager: U128,
+    token: Option<AccountId>,
+    pot: U128,
+    rated: bool,
+
+    // Not useful to clients, and would make the status a lot bigger.
+    #[serde(skip)]
+    positions: Vec<Vec<u8>>,
+    // Can get long, so it's only returned a page at a time.
+    #[serde(skip)]
+    history: Vec<MoveRecord>,
 }
 
 impl Default for Game{
     fn default() -> Self{
         // let squares: [u8; 64] = Board::get_default_board();
         let squares: Vec<u8> = Vec::from(Board::get_default_board());
+        // The starting position counts for repetition too.
+        let positions: Vec<Vec<u8>> = vec![Board::default().get_position_key(false)];
 
         Game{
             squares,
             turn: 0,
+            start_turn: 0,
             player_turn: false,
-            game_over: false,
+            result: GameResult::Running,
             black_check: false,
             white_check: false,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            white: AccountId::new(),
+            black: AccountId::new(),
+            created_at: U64(0),
+            start_fen: None,
+            draw_offer: None,
+            clock: None,
+            correspondence: None,
+            wager: U128(0),
+            token: None,
+            pot: U128(0),
+            rated: false,
+            positions,
+            history: Vec::new(),
         }
     }
 }
@@ -74,32 +179,55 @@ impl Game{
 
         self.turn = turn.get_value();
         self.player_turn = turn.get_current_player_boolean();
-        // self.game_over = turn.is_game_over();
         self.black_check = turn.is_black_check();
         self.white_check = turn.is_white_check();
-        if turn.is_checkmate(){
-            self.game_over = true;
+        self.castling = board.get_castling_rights();
+        self.en_passant = board.get_en_passant();
+        self.halfmove_clock = turn.get_halfmove_clock();
+        self.result = turn.get_result();
+
+        // Positions before a capture or pawn move can't happen again.
+        if self.halfmove_clock == 0 {
+            self.positions.clear();
+        }
+        self.positions.push(board.get_position_key(self.player_turn));
+
+        // The fifth time the same position happens, the game ends on its own.
+        if (self.count_repetitions() >= 5) && !self.result.is_game_over() {
+            self.result = GameResult::Draw(DrawReason::FivefoldRepetition);
         }
     }
 
+    /// True if the account plays either color in this game.
+    #[private]
+    fn is_player(&self, account_id: &AccountId) -> bool {
+        (*account_id == self.white) || (*account_id == self.black)
+    }
 
+    /// How many times the current position happened since the last capture or pawn move (including now).
     #[private]
-    fn handle_error(&mut self, error: ErrorResponse) -> String {
-        let message: &str = match error{
-            // If movement starts in a position that has no piece.
-            ErrorResponse::NoPiece => {"No piece found in given position. "},
-            // If movement starts in a position owned by a rival player
-            ErrorResponse::RivalPiece => {"A rival player owns this piece. "},
-            // If target position is invalid for given piece
-            ErrorResponse::InvalidMove => {"Invalid move. "},
-            // If the game is already over.
-            ErrorResponse::GameOver => {
-                self.game_over = true;
-                "Game is already over"
-            },
+    fn count_repetitions(&self) -> usize {
+        let current: &Vec<u8> = match self.positions.last() {
+            Some(key) => key,
+            None => return 0,
         };
 
-        String::from(message)
+        self.positions
+            .iter()
+            .filter(|key| *key == current)
+            .count()
+    }
+
+
+    /// Update the game for errors that end it, then return the error for the caller.
+    #[private]
+    fn handle_error(&mut self, error: ErrorResponse) -> ErrorResponse {
+        // Turn only returns GameOver when it reaches the limit of 255.
+        if (error == ErrorResponse::GameOver) && !self.result.is_game_over() {
+            self.result = GameResult::Draw(DrawReason::TurnLimit);
+        }
+
+        error
     }
 
     #[private]
@@ -112,6 +240,11 @@ impl Game{
         self.turn.clone()
     }
 
+    #[private]
+    pub fn get_start_turn(&self) -> u32 {
+        self.start_turn
+    }
+
     #[private]
     pub fn get_player_turn(&self) -> bool {
         self.player_turn.clone()
@@ -119,7 +252,12 @@ impl Game{
 
     #[private]
     pub fn is_game_over(&self) -> bool {
-        self.game_over.clone()
+        self.result.is_game_over()
+    }
+
+    #[private]
+    pub fn get_result(&self) -> GameResult {
+        self.result
     }
 
     #[private]
@@ -133,62 +271,383 @@ impl Game{
     }
 
     #[private]
-    pub fn reset_game_game(&mut self) -> String{
-        self.squares = Vec::from(Board::get_default_board());
-        self.turn = 0;
-        self.player_turn = false;
-        self.game_over = false;
+    pub fn get_castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
 
-        String::from("Reset successful.")
+    #[private]
+    pub fn get_en_passant(&self) -> Option<u8> {
+        self.en_passant
     }
 
+    #[private]
+    pub fn get_halfmove_clock(&self) -> u8 {
+        self.halfmove_clock
+    }
 
     #[private]
-    pub fn get_game_status_game(&self) -> Self{
-        let game_running: &str = match self.game_over{
-            false => "Running",
-            true => "Game Over",
-        };
+    pub fn get_white_player(&self) -> AccountId {
+        self.white.clone()
+    }
 
-        let turn: u8 = self.turn.clone();
+    #[private]
+    pub fn get_black_player(&self) -> AccountId {
+        self.black.clone()
+    }
 
-        let player: &str = match self.player_turn {
-            false => "White",
-            true => "Black",
-        };
+    #[private]
+    pub fn get_created_at(&self) -> U64 {
+        self.created_at
+    }
 
-        log(&format!("Game Status: {}\nTurn: {}\nCurrent player: {}\n",
-            game_running,
-            turn,
-            player,
-        ));
+    #[private]
+    pub fn get_start_fen(&self) -> Option<String> {
+        self.start_fen.clone()
+    }
 
-        log(
-            &format!("\nBoard:\n{}\n", 
-                Board::get_board_string(
-                    &self.squares[..],              // squares: &[u8; 64]
-                )
-            )
-        );
+    #[private]
+    pub fn get_draw_offer(&self) -> Option<AccountId> {
+        self.draw_offer.clone()
+    }
+
+    #[private]
+    pub fn get_time_control(&self) -> Option<TimeControl> {
+        self.clock.as_ref().map(|clock| clock.get_time_control())
+    }
 
+    /// Remaining time of each player right now, if the game has a clock.
+    #[private]
+    pub fn get_clock_game(&self) -> Option<Clock> {
+        match &self.clock {
+            None => None,
+            Some(clock) if self.is_game_over() => Some(clock.clone()),
+            Some(clock) => Some(clock.get_clock_at(
+                self.player_turn,               // player_turn: bool,
+                env::block_timestamp(),         // now: u64,
+            )),
+        }
+    }
+
+    #[private]
+    pub fn get_correspondence_game(&self) -> Option<Correspondence> {
+        self.correspondence.clone()
+    }
+
+    #[private]
+    pub fn get_wager(&self) -> U128 {
+        self.wager
+    }
+
+    #[private]
+    pub fn get_token(&self) -> Option<AccountId> {
+        self.token.clone()
+    }
+
+    #[private]
+    pub fn get_pot(&self) -> U128 {
+        self.pot
+    }
+
+    #[private]
+    pub fn is_rated(&self) -> bool {
+        self.rated
+    }
+
+    /// Number of moves stored in history.
+    #[private]
+    pub fn get_history_length_game(&self) -> u64 {
+        self.history.len() as u64
+    }
+
+    /// Up to limit moves from history, starting at from_index (0 is the first move of the game).
+    #[private]
+    pub fn get_history_game(&self, from_index: u64, limit: u64) -> Vec<MoveRecord> {
+        self.history
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+
+    /// Start a new game. The caller plays white, opponent plays black.
+    /// Only its players can reset it, running or not.
+    #[private]
+    pub fn reset_game_game(&mut self, opponent: AccountId) -> String{
+        match self.reset(
+            opponent,                           // opponent: AccountId,
+        ) {
+            Result::Err(message) => message,
+            Result::Ok(()) => String::from("Reset successful."),
+        }
+    }
+
+
+    #[private]
+    pub fn get_game_status_game(&self) -> Self{
         // let squares: [u8; 64] = self.squares.clone();
         let squares: Vec<u8> = self.squares.clone();
         let turn: u8 = self.turn. clone();
+        let start_turn: u32 = self.start_turn;
         let player_turn: bool = self.player_turn.clone();
-        let game_over: bool = self.game_over.clone();
+        let result: GameResult = self.result;
         let black_check: bool = self.black_check.clone();
         let white_check: bool = self.white_check.clone();
+        let castling: CastlingRights = self.castling;
+        let en_passant: Option<u8> = self.en_passant;
+        let halfmove_clock: u8 = self.halfmove_clock;
+        let white: AccountId = self.white.clone();
+        let black: AccountId = self.black.clone();
+        let created_at: U64 = self.created_at;
+        let start_fen: Option<String> = self.start_fen.clone();
+        let draw_offer: Option<AccountId> = self.draw_offer.clone();
+        let clock: Option<Clock> = self.clock.clone();
+        let correspondence: Option<Correspondence> = self.correspondence.clone();
+        let wager: U128 = self.wager;
+        let token: Option<AccountId> = self.token.clone();
+        let pot: U128 = self.pot;
+        let rated: bool = self.rated;
+        let positions: Vec<Vec<u8>> = self.positions.clone();
+        let history: Vec<MoveRecord> = self.history.clone();
 
         Game{
             squares,
             turn,
+            start_turn,
             player_turn,
-            game_over,
+            result,
             black_check,
             white_check,
+            castling,
+            en_passant,
+            halfmove_clock,
+            white,
+            black,
+            created_at,
+            start_fen,
+            draw_offer,
+            clock,
+            correspondence,
+            wager,
+            token,
+            pot,
+            rated,
+            positions,
+            history,
+        }
+    }
+
+
+    /// A player can claim a draw after 50 moves each without any capture or pawn move,
+    /// or when the same position happens for the third time.
+    #[private]
+    pub fn claim_draw_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        if !self.is_player(&env::predecessor_account_id()) {
+            return String::from("Only the players can claim a draw.");
+        }
+
+        if self.count_repetitions() >= 3 {
+            self.result = GameResult::Draw(DrawReason::ThreefoldRepetition);
+
+            return String::from("Draw claimed by threefold repetition.");
+        }
+
+        if self.halfmove_clock >= 100 {
+            self.result = GameResult::Draw(DrawReason::FiftyMoveRule);
+
+            return String::from("Draw claimed by the fifty-move rule.");
+        }
+
+        String::from("Draw can't be claimed yet.")
+    }
+
+    /// The caller gives up, the rival wins.
+    #[private]
+    pub fn resign_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !self.is_player(&account_id) {
+            return String::from("Only the players can resign.");
+        }
+
+        self.result = match account_id == self.white {
+            true => GameResult::BlackWins(WinReason::Resignation),
+            false => GameResult::WhiteWins(WinReason::Resignation),
+        };
+        self.draw_offer = None;
+
+        format!("{}.", self.result.get_description())
+    }
+
+    /// Call the game off before both players moved. Nobody wins, and each wager is given back.
+    #[private]
+    pub fn abort_game_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        if !self.is_player(&env::predecessor_account_id()) {
+            return String::from("Only the players can abort a game.");
+        }
+
+        if self.history.len() >= 2 {
+            return String::from("Both players already moved, the game can't be aborted.");
+        }
+
+        self.result = GameResult::Aborted;
+        self.draw_offer = None;
+
+        String::from("Game aborted.")
+    }
+
+    /// The caller offers a draw. It stays until the rival answers or the caller moves.
+    #[private]
+    pub fn offer_draw_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !self.is_player(&account_id) {
+            return String::from("Only the players can offer a draw.");
+        }
+
+        match &self.draw_offer {
+            Some(offering) if *offering == account_id => String::from("You already offered a draw."),
+            Some(_) => String::from("Your rival already offered a draw, call accept_draw to take it."),
+            None => {
+                self.draw_offer = Some(account_id);
+
+                String::from("Draw offered.")
+            },
         }
     }
 
+    /// The caller accepts the draw offered by the rival, ending the game.
+    #[private]
+    pub fn accept_draw_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !self.is_player(&account_id) {
+            return String::from("Only the players can accept a draw.");
+        }
+
+        match &self.draw_offer {
+            Some(offering) if *offering != account_id => {
+                self.result = GameResult::Draw(DrawReason::Agreement);
+                self.draw_offer = None;
+
+                format!("{}.", self.result.get_description())
+            },
+            _ => String::from("There's no draw offer from your rival."),
+        }
+    }
+
+    /// The caller declines the draw offered by the rival. The game goes on.
+    #[private]
+    pub fn decline_draw_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !self.is_player(&account_id) {
+            return String::from("Only the players can decline a draw.");
+        }
+
+        match &self.draw_offer {
+            Some(offering) if *offering != account_id => {
+                self.draw_offer = None;
+
+                String::from("Draw declined.")
+            },
+            _ => String::from("There's no draw offer from your rival."),
+        }
+    }
+
+    /// The caller wins if the rival ran out of time in their turn.
+    /// It's a draw if the caller can't checkmate with their pieces, whatever the rival does.
+    #[private]
+    pub fn claim_timeout_game(&mut self) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !self.is_player(&account_id) {
+            return String::from("Only the players can claim a timeout.");
+        }
+
+        if self.clock.is_none() && self.correspondence.is_none() {
+            return String::from("This game has no clock.");
+        }
+
+        if self.get_player_account() == account_id {
+            return String::from("Only the rival of the player to move can claim a timeout.");
+        }
+
+        if !self.is_out_of_time(env::block_timestamp()) {
+            return String::from("Your rival still has time left.");
+        }
+
+        self.set_timeout_result();
+
+        format!("{}.", self.result.get_description())
+    }
+
+    /// In a correspondence game, the player to move pushes their deadline by days, from their vacation days.
+    #[private]
+    pub fn take_vacation_game(&mut self, days: u32) -> String {
+        if self.is_game_over(){
+            return String::from("Game already over, check status or call reset.");
+        }
+
+        if self.get_player_account() != env::predecessor_account_id() {
+            return String::from("Only the player to move can take vacation days.");
+        }
+
+        if self.is_out_of_time(env::block_timestamp()) {
+            return String::from("Time already ran out for this move.");
+        }
+
+        let player_turn: bool = self.player_turn;
+        let correspondence: &mut Correspondence = match &mut self.correspondence {
+            None => return String::from("Only correspondence games have vacation days."),
+            Some(correspondence) => correspondence,
+        };
+
+        if days == 0 {
+            return String::from("Must take at least 1 vacation day.");
+        }
+
+        match correspondence.take_vacation(player_turn, days) {
+            false => format!("Only {} vacation days left.", correspondence.get_vacation_left(player_turn)),
+            true => format!("Vacation taken, {} days left.", correspondence.get_vacation_left(player_turn)),
+        }
+    }
+
+    /// The current position in FEN.
+    #[private]
+    pub fn get_fen_game(&self) -> String {
+        fen::get_fen(
+            &self.squares,                      // squares: &[u8],
+            self.player_turn,                   // player_turn: bool,
+            &self.castling,                     // castling: &CastlingRights,
+            self.en_passant,                    // en_passant: Option<u8>,
+            self.halfmove_clock,                // halfmove_clock: u8,
+            self.start_turn + (self.turn as u32),// turn: u32,
+        )
+    }
 
     #[private]
     pub fn get_board_game(&self) -> String {
@@ -221,60 +680,334 @@ impl Game{
     }
 
 
+    /// Every move the player of the current color can do. Empty if the game is over.
+    #[private]
+    pub fn get_legal_moves_game(&self) -> Vec<LegalMove> {
+        self.list_legal_moves(
+            None,                               // from: Option<u8>,
+        )
+    }
+
+    /// Every move the piece in square (like "e2") can do. Empty if it can't move, it's not the current color,
+    /// or square isn't a file and a rank.
+    #[private]
+    pub fn get_legal_moves_from_game(&self, square: String) -> Vec<LegalMove> {
+        let from: u8 = match fen::get_square_index(&square) {
+            None => return Vec::new(),
+            Some(index) => index,
+        };
+
+        self.list_legal_moves(
+            Some(from),                         // from: Option<u8>,
+        )
+    }
+
+
+    /// Same as move_to_game, with the move in Standard Algebraic Notation, like "Nf3", "exd5", "O-O" or "e8=Q".
+    #[private]
+    pub fn move_san_game(&mut self, san: String) -> MoveOutcome {
+        if self.is_game_over(){
+            return MoveOutcome::rejected(ErrorResponse::GameOver);
+        }
+
+        let (current, target, promotion): (u8, u8, Option<Promotion>) = match self.find_san_move(&san) {
+            None => return MoveOutcome::rejected(ErrorResponse::NoMatchingMove),
+            Some(found) => found,
+        };
+
+        self.move_to_game(
+            current,                            // current: u8,
+            target,                             // target: u8,
+            promotion,                          // promotion: Option<Promotion>,
+        )
+    }
+
+    /// Move a piece for the account calling the contract.
     #[private]
     pub fn move_to_game(
         &mut self,
         current: u8,
         target: u8,
-    ) -> String {
-        if self.game_over{
-            return String::from("Game already over, check status or call reset.");
+        promotion: Option<Promotion>,
+    ) -> MoveOutcome {
+        match self.move_caller(
+            current,                            // current: u8,
+            target,                             // target: u8,
+            promotion,                          // promotion: Option<Promotion>,
+        ) {
+            Result::Err(error) => MoveOutcome::rejected(error),
+            Result::Ok(()) => MoveOutcome::Moved{
+                record: self.history[self.history.len() - 1].clone(),
+                result: self.result,
+            },
+        }
+    }
+
+    /// Same as move_to_game, with the move in UCI notation, like "e2e4" or "e7e8q".
+    #[private]
+    pub fn move_uci_game(&mut self, uci: String) -> MoveOutcome {
+        let (current, target, promotion): (u8, u8, Option<Promotion>) = match uci::parse_uci(&uci) {
+            None => return MoveOutcome::rejected(ErrorResponse::InvalidNotation),
+            Some(found) => found,
+        };
+
+        self.move_to_game(
+            current,                            // current: u8,
+            target,                             // target: u8,
+            promotion,                          // promotion: Option<Promotion>,
+        )
+    }
+
+    #[private]
+    fn move_caller(
+        &mut self,
+        current: u8,
+        target: u8,
+        promotion: Option<Promotion>,
+    ) -> Result<(), ErrorResponse> {
+        if self.is_game_over(){
+            return Result::Err(ErrorResponse::GameOver);
         }
 
         if (current > 63) || (target > 63) {
-            return String::from("Invalid Arguments. Must be lower than 64.");
+            return Result::Err(ErrorResponse::OutOfRange);
         }
 
+        // A move after the time ran out is too late, and ends the game.
+        let now: u64 = env::block_timestamp();
+        if self.is_out_of_time(now) && (self.get_player_account() == env::predecessor_account_id()) {
+            self.set_timeout_result();
+            return Result::Err(ErrorResponse::OutOfTime);
+        }
+
+        let player_turn: bool = self.player_turn;
+        match self.play_move(
+            current,                            // current: u8,
+            target,                             // target: u8,
+            promotion,                          // promotion: Option<Promotion>,
+            &env::predecessor_account_id(),     // account_id: &AccountId,
+        ) {
+            Result::Err(err) => Result::Err(self.handle_error(
+                err,                            // error: ErrorResponse,
+            )),
+            Result::Ok(()) => {
+                if let Some(clock) = &mut self.clock {
+                    clock.punch(
+                        player_turn,            // player_turn: bool,
+                        now,                    // now: u64,
+                    );
+                }
+                if let Some(correspondence) = &mut self.correspondence {
+                    correspondence.next_turn(
+                        now,                    // now: u64,
+                    );
+                }
+
+                Result::Ok(())
+            },
+        }
+    }
+}
+
+
+// Methods that can't be called from outside the contract.
+impl Game{
+    /// Start the game over with the caller as white against opponent.
+    /// The error is the message for the caller.
+    pub fn reset(&mut self, opponent: AccountId) -> Result<(), String> {
+        let account_id: AccountId = env::predecessor_account_id();
+
+        // The wager was put for these players and this game, it can't start over.
+        if self.wager.0 > 0 {
+            return Result::Err(String::from("Games with a wager can't be reset."));
+        }
+        if self.rated {
+            return Result::Err(String::from("Rated games can't be reset."));
+        }
+
+        // A new game has no players yet. A finished one is their record, so others can't wipe it either.
+        if !self.white.is_empty() && !self.is_player(&account_id) {
+            return Result::Err(String::from("Only the players can reset a game."));
+        }
+
+        if opponent == account_id {
+            return Result::Err(String::from("Can't play against yourself."));
+        }
+
+        self.start(
+            account_id,                         // white: AccountId,
+            opponent,                           // black: AccountId,
+        );
+
+        Result::Ok(())
+    }
+
+    /// Start the game over from the usual position, between white and black.
+    pub fn start(&mut self, white: AccountId, black: AccountId) {
+        self.white = white;
+        self.black = black;
+        self.squares = Vec::from(Board::get_default_board());
+        self.turn = 0;
+        self.start_turn = 0;
+        self.player_turn = false;
+        self.result = GameResult::Running;
+        self.castling = CastlingRights::default();
+        self.en_passant = None;
+        self.halfmove_clock = 0;
+        self.positions = vec![Board::default().get_position_key(false)];
+        self.history.clear();
+        self.created_at = U64(env::block_timestamp());
+        self.start_fen = None;
+        self.draw_offer = None;
+        // Same time control, with the whole time again.
+        if let Some(clock) = &mut self.clock {
+            clock.restart(self.created_at.0);
+        }
+        if let Some(correspondence) = &mut self.correspondence {
+            correspondence.restart(self.created_at.0);
+        }
+    }
+
+    /// Give the game a clock, starting with white's time at the creation of the game.
+    pub fn set_time_control(&mut self, time_control: TimeControl) {
+        self.clock = Some(Clock::new(
+            time_control,                       // time_control: TimeControl,
+            self.created_at.0,                  // now: u64,
+        ));
+    }
+
+    /// Make it a correspondence game, with the first deadline counted from the creation of the game.
+    pub fn set_correspondence(&mut self, days_per_move: u32, vacation_days: u32) {
+        self.correspondence = Some(Correspondence::new(
+            days_per_move,                      // days_per_move: u32,
+            vacation_days,                      // vacation_days: u32,
+            self.created_at.0,                  // now: u64,
+        ));
+    }
+
+    /// Each player puts wager of token (None for NEAR) in the pot, which the contract holds until the game ends.
+    pub fn set_wager(&mut self, wager: u128, token: Option<AccountId>) {
+        self.wager = U128(wager);
+        self.token = token;
+        self.pot = U128(wager * 2);
+    }
+
+    pub fn set_rated(&mut self, rated: bool) {
+        self.rated = rated;
+    }
+
+    /// Who gets the pot (and how much) once the game is over: all to the winner, half each otherwise.
+    /// Empties the pot, so it's only paid once.
+    pub fn take_payouts(&mut self) -> Vec<(AccountId, u128)> {
+        let pot: u128 = self.pot.0;
+        if pot == 0 || !self.is_game_over() {
+            return Vec::new();
+        }
+        self.pot = U128(0);
+
+        match self.result {
+            GameResult::WhiteWins(_) => vec![(self.white.clone(), pot)],
+            GameResult::BlackWins(_) => vec![(self.black.clone(), pot)],
+            _ => vec![
+                (self.white.clone(), pot / 2),
+                (self.black.clone(), pot - pot / 2),
+            ],
+        }
+    }
+
+    /// True if the player to move ran out of time, by the clock or the correspondence deadline.
+    fn is_out_of_time(&self, now: u64) -> bool {
+        let flag_down: bool = match &self.clock {
+            None => false,
+            Some(clock) => clock.is_flag_down(self.player_turn, now),
+        };
+        let expired: bool = match &self.correspondence {
+            None => false,
+            Some(correspondence) => correspondence.is_expired(now),
+        };
+
+        flag_down || expired
+    }
+
+    /// Account playing the color that moves next.
+    pub fn get_player_account(&self) -> AccountId {
+        match self.player_turn {
+            false => self.white.clone(),
+            true => self.black.clone(),
+        }
+    }
+
+    /// The player to move ran out of time. The rival wins, unless they can't checkmate with the pieces on the board.
+    fn set_timeout_result(&mut self) {
+        let rival_color: Color = match self.player_turn {
+            false => Color::BLACK,
+            true => Color::WHITE,
+        };
+
+        self.result = match (self.build_board().is_insufficient_material_for(&rival_color), self.player_turn) {
+            (true, _) => GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial),
+            (false, false) => GameResult::BlackWins(WinReason::Timeout),
+            (false, true) => GameResult::WhiteWins(WinReason::Timeout),
+        };
+        self.draw_offer = None;
+    }
+
+    /// Move a piece for account_id, which must be the player of the current color.
+    /// Updates the game state and history if the move is valid.
+    pub fn play_move(
+        &mut self,
+        current: u8,
+        target: u8,
+        promotion: Option<Promotion>,
+        account_id: &AccountId,
+    ) -> Result<(), ErrorResponse> {
         let current_player: bool = self.player_turn;
         let turn: u8 = self.turn;
         
         // let squares: [u8; 64] = self.squares.clone();
         let mut squares: [u8; 64] = [0; 64];
-        for index in 0..self.squares.len(){
-            squares[index] = self.squares[index];
-        }
+        squares.copy_from_slice(&self.squares);
 
-        log(&format!("Creating Board"));
         let mut board: Board = Board::new(
             squares,                            // squares: [u8; 64]
         );
+        board.set_castling_rights(self.castling);
+        board.set_en_passant(self.en_passant);
 
-        log(&format!("Creating Players"));
         let mut players: Players = Players::new(
             &board,                             // board: &Board, 
             current_player,                     // current_player: bool, 
             turn,                               // turn: u8,
+            self.halfmove_clock,                // halfmove_clock: u8,
+            self.white.clone(),                 // white_account: AccountId,
+            self.black.clone(),                 // black_account: AccountId,
         );
 
-        // using env::log here just to clear linter warning
-        env::log(&format!("Creating Step").as_bytes());
-        let step: Step = Step::new_index(
+        let mut step: Step = Step::new_index(
             current,                            // current: u8,
             target,                             // target: u8,
             &board,                             // board: &Board,
         );
+        step.set_promotion(promotion);
 
-        log(&format!("Calling move"));
         match players.move_to(
             &mut board,                         // board: &mut Board, 
-            step,                               // mut target: Step,
+            step.clone(),                       // mut target: Step,
+            account_id,                         // account_id: &AccountId,
         ) {
-            Result::Err(err) => {
-                return self.handle_error(
-                    err,                        // error: ErrorResponse,
-                );
-            },
+            Result::Err(err) => Result::Err(err),
             _ => {
+                // Notation is written from the position before the move.
+                let mut previous_board: Board = Board::new(
+                    squares,                    // squares: [u8; 64]
+                );
+                previous_board.set_castling_rights(self.castling);
+                previous_board.set_en_passant(self.en_passant);
+                let step_san: String = san::get_san(
+                    &mut previous_board,        // board: &mut Board,
+                    &step,                      // step: &Step,
+                );
+
                 // Copy the values from the virtual board to the machine state one.
                 let game_state: Turn = players.get_turn();
                 self.update_game_state(
@@ -282,6 +1015,23 @@ impl Game{
                     game_state,
                 );
 
+                // Moving again takes back the draw offer.
+                if self.draw_offer.as_ref() == Some(account_id) {
+                    self.draw_offer = None;
+                }
+
+                // The rival is the next player now.
+                let check: bool = match self.player_turn {
+                    false => self.white_check,
+                    true => self.black_check,
+                };
+                self.history.push(MoveRecord::new(
+                    &step,                      // step: &Step,
+                    &step_san,                  // san: &str,
+                    check,                      // check: bool,
+                    players.get_turn().is_checkmate(),// checkmate: bool,
+                ));
+
                 // self.squares.clear();
                 // for piece_number in board.get_board_array(){
                 //     self.squares.push(piece_number);
@@ -305,9 +1055,264 @@ impl Game{
                 //     }
                 // }
 
-                return String::from("Move successful.");
+                Result::Ok(())
             }
         }
     }
+
+    /// Replace the position with the one in a FEN string. Players stay the same, history starts over.
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        let position: FenPosition = fen::parse_fen(fen)?;
+
+        let mut board: Board = Board::new(
+            position.squares,                   // squares: [u8; 64]
+        );
+        board.set_castling_rights(position.castling);
+        board.set_en_passant(position.en_passant);
+
+        self.squares = Vec::from(position.squares);
+        // The limit of 255 turns only counts the ones played here.
+        self.turn = 0;
+        self.start_turn = position.turn;
+        self.player_turn = position.player_turn;
+        self.castling = position.castling;
+        self.en_passant = position.en_passant;
+        self.halfmove_clock = position.halfmove_clock;
+        self.positions = vec![board.get_position_key(position.player_turn)];
+        self.history.clear();
+        self.start_fen = Some(String::from(fen.trim()));
+
+        let (color, rival_color): (Color, Color) = match position.player_turn {
+            false => (Color::WHITE, Color::BLACK),
+            true => (Color::BLACK, Color::WHITE),
+        };
+
+        // The rival reaching our king means we're under check.
+        let mut rival: Player = Player::new(
+            rival_color,                        // color: Color,
+            &board,                             // board: &Board,
+        );
+        rival.build_reports(
+            &board,                             // board: &Board,
+            false,                              // search_checkmate: bool,
+        );
+        let check: bool = rival.is_check();
+        self.white_check = check && !position.player_turn;
+        self.black_check = check && position.player_turn;
+
+        let mut current: Player = Player::new(
+            color,                              // color: Color,
+            &board,                             // board: &Board,
+        );
+        current.build_reports(
+            &board,                             // board: &Board,
+            false,                              // search_checkmate: bool,
+        );
+
+        // The position might already be the end of the game.
+        self.result = match (current.can_avoid_checkmate(&mut board), check) {
+            (false, true) => match position.player_turn {
+                false => GameResult::BlackWins(WinReason::Checkmate),
+                true => GameResult::WhiteWins(WinReason::Checkmate),
+            },
+            (false, false) => GameResult::Draw(DrawReason::Stalemate),
+            (true, _) if board.is_insufficient_material() => GameResult::Draw(DrawReason::InsufficientMaterial),
+            (true, _) => GameResult::Running,
+        };
+
+        Result::Ok(())
+    }
+
+    /// Board with the current state of the game.
+    fn build_board(&self) -> Board {
+        let mut squares: [u8; 64] = [0; 64];
+        squares.copy_from_slice(&self.squares);
+
+        let mut board: Board = Board::new(
+            squares,                            // squares: [u8; 64]
+        );
+        board.set_castling_rights(self.castling);
+        board.set_en_passant(self.en_passant);
+
+        board
+    }
+
+    /// The move written as text in SAN for the current player, as (current index, target index, promotion).
+    /// None if no valid move matches it.
+    fn find_san_move(&self, text: &str) -> Option<(u8, u8, Option<Promotion>)> {
+        let color: Color = match self.player_turn {
+            false => Color::WHITE,
+            true => Color::BLACK,
+        };
+
+        let mut board: Board = self.build_board();
+        let mut player: Player = Player::new(
+            color,                              // color: Color,
+            &board,                             // board: &Board,
+        );
+        player.build_reports(
+            &board,                             // board: &Board,
+            false,                              // search_checkmate: bool,
+        );
+        let valid_steps: Vec<Step> = player.get_valid_steps(
+            &mut board,                         // board: &mut Board,
+        );
+
+        let step: Step = san::find_step(&valid_steps, text)?;
+
+        let current: u8 = Report::col_row_to_index(
+            step.current_piece.get_column(),    // col: u8,
+            step.current_piece.get_row(),       // row: u8,
+        );
+        let target: u8 = Report::col_row_to_index(
+            step.target_piece.get_column(),     // col: u8,
+            step.target_piece.get_row(),        // row: u8,
+        );
+
+        Some((current, target, step.promotion))
+    }
+
+    /// Every legal move of the current player, only the ones starting at from if given.
+    /// Promotions are listed once for each piece the pawn can become.
+    fn list_legal_moves(&self, from: Option<u8>) -> Vec<LegalMove> {
+        let mut legal_moves: Vec<LegalMove> = Vec::new();
+        if self.is_game_over() {
+            return legal_moves;
+        }
+
+        let color: Color = match self.player_turn {
+            false => Color::WHITE,
+            true => Color::BLACK,
+        };
+
+        let mut board: Board = self.build_board();
+        let mut player: Player = Player::new(
+            color,                      // color: Color,
+            &board,                             // board: &Board,
+        );
+        player.build_reports(
+            &board,                             // board: &Board,
+            false,                              // search_checkmate: bool,
+        );
+        let valid_steps: Vec<Step> = player.get_valid_steps(
+            &mut board,                         // board: &mut Board,
+        );
+
+        for step in valid_steps {
+            let current: u8 = Report::col_row_to_index(
+                step.current_piece.get_column(),// col: u8,
+                step.current_piece.get_row(),   // row: u8,
+            );
+            if from.is_some() && (from != Some(current)) {
+                continue;
+            }
+
+            let promotions: Vec<Option<Promotion>> = match step.is_promotion() {
+                false => vec![None],
+                true => vec![
+                    Some(Promotion::QUEEN),
+                    Some(Promotion::ROOK),
+                    Some(Promotion::BISHOP),
+                    Some(Promotion::KNIGHT),
+                ],
+            };
+
+            for promotion in promotions {
+                let mut candidate: Step = step.clone();
+                candidate.set_promotion(promotion);
+
+                let step_san: String = san::get_san(
+                    &mut board,                 // board: &mut Board,
+                    &candidate,                 // step: &Step,
+                );
+
+                // Does the piece that moved, or one it uncovered, reach the rival king?
+                candidate.commit_move(
+                    &mut board,                 // board: &mut Board,
+                );
+                let mut mover: Player = Player::new(
+                    color,              // color: Color,
+                    &board,                     // board: &Board,
+                );
+                mover.build_reports(
+                    &board,                     // board: &Board,
+                    true,                       // search_checkmate: bool,
+                );
+                candidate.revert_move(
+                    &mut board,                 // board: &mut Board,
+                );
+                let check: bool = mover.is_check();
+
+                // Only a check can be checkmate, so the whole move is played just for those.
+                let checkmate: bool = check && self.is_checkmate_after(&candidate);
+
+                legal_moves.push(LegalMove::new(
+                    &candidate,                 // step: &Step,
+                    &step_san,                  // san: &str,
+                    check,                      // check: bool,
+                    checkmate,                  // checkmate: bool,
+                ));
+            }
+        }
+
+        legal_moves
+    }
+
+    /// Play step on a copy of the game state and tell if it ends in checkmate.
+    fn is_checkmate_after(&self, step: &Step) -> bool {
+        let mut board: Board = self.build_board();
+        let mut players: Players = Players::new(
+            &board,                             // board: &Board,
+            self.player_turn,                   // current_player: bool,
+            self.turn,                          // turn: u8,
+            self.halfmove_clock,                // halfmove_clock: u8,
+            self.white.clone(),                 // white_account: AccountId,
+            self.black.clone(),                 // black_account: AccountId,
+        );
+
+        let account_id: AccountId = self.get_player_account();
+
+        match players.move_to(
+            &mut board,                         // board: &mut Board,
+            step.clone(),                       // mut target: Step,
+            &account_id,                        // account_id: &AccountId,
+        ) {
+            Result::Err(_) => false,
+            Result::Ok(()) => players.get_turn().is_checkmate(),
+        }
+    }
+
+    /// Play every move of a PGN, starting from its FEN tag if there is one.
+    /// Each move goes through the same validation as move_to, by the account of the color moving.
+    /// Stops at the first invalid move, returning its ply number (1 is the first move) and why it failed.
+    pub fn load_pgn(&mut self, pgn_text: &str) -> Result<(), String> {
+        if let Some(fen_text) = pgn::get_tag(pgn_text, "FEN") {
+            if let Result::Err(err) = self.load_fen(&fen_text) {
+                return Result::Err(format!("Invalid FEN tag. {}", err.get_message()));
+            }
+        }
+
+        for (index, text) in pgn::get_san_moves(pgn_text).iter().enumerate() {
+            let ply: usize = index + 1;
+
+            if self.is_game_over() {
+                return Result::Err(format!("Invalid move at ply {} ({}). Game is already over.", ply, text));
+            }
+
+            let account_id: AccountId = self.get_player_account();
+
+            let (current, target, promotion): (u8, u8, Option<Promotion>) = match self.find_san_move(text) {
+                None => return Result::Err(format!("Invalid move at ply {} ({}). No valid move matches it.", ply, text)),
+                Some(found) => found,
+            };
+
+            if let Result::Err(err) = self.play_move(current, target, promotion, &account_id) {
+                let error: ErrorResponse = self.handle_error(err);
+                return Result::Err(format!("Invalid move at ply {} ({}). {}", ply, text, error.get_message()));
+            }
+        }
+
+        Result::Ok(())
+    }
 }
 
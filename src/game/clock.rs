@@ -0,0 +1,142 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+
+// Block timestamps are in nanoseconds.
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+/// Most seconds a time control can give as base time, and as increment. Longer games can be played by correspondence.
+pub const MAX_SECONDS: u32 = 7 * 86_400;
+
+
+/// How the increment is given back after each move.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ClockMode{
+    // The whole increment is added after every move.
+    Fischer,
+    // The time spent in the move is given back, up to the increment.
+    Bronstein,
+}
+
+/// Time each player has for the whole game.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimeControl{
+    base_seconds: u32,
+    increment_seconds: u32,
+    mode: ClockMode,
+}
+
+impl TimeControl{
+    pub fn new(base_seconds: u32, increment_seconds: u32, mode: ClockMode) -> Self {
+        TimeControl{
+            base_seconds,
+            increment_seconds,
+            mode,
+        }
+    }
+
+    pub fn get_base_seconds(&self) -> u32 {
+        self.base_seconds
+    }
+
+    pub fn get_increment_seconds(&self) -> u32 {
+        self.increment_seconds
+    }
+
+    pub fn get_mode(&self) -> ClockMode {
+        self.mode
+    }
+}
+
+
+/// Remaining time of each player, in nanoseconds.
+/// The time of the player to move runs from turn_started_at.
+#[derive(Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Clock{
+    time_control: TimeControl,
+    white_remaining: U64,
+    black_remaining: U64,
+    turn_started_at: U64,
+}
+
+impl Clock{
+    /// Both players with the whole base time. White's time starts running at now.
+    pub fn new(time_control: TimeControl, now: u64) -> Self {
+        let base: u64 = (time_control.base_seconds as u64) * NANOSECONDS_PER_SECOND;
+
+        Clock{
+            time_control,
+            white_remaining: U64(base),
+            black_remaining: U64(base),
+            turn_started_at: U64(now),
+        }
+    }
+
+    pub fn get_time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    pub fn get_white_remaining(&self) -> U64 {
+        self.white_remaining
+    }
+
+    pub fn get_black_remaining(&self) -> U64 {
+        self.black_remaining
+    }
+
+    /// Remaining time at now of the player to move (false: White, true: Black).
+    fn get_remaining(&self, player_turn: bool, now: u64) -> u64 {
+        let remaining: u64 = match player_turn {
+            false => self.white_remaining.0,
+            true => self.black_remaining.0,
+        };
+
+        remaining.saturating_sub(now.saturating_sub(self.turn_started_at.0))
+    }
+
+    /// True if the player to move has no time left at now.
+    pub fn is_flag_down(&self, player_turn: bool, now: u64) -> bool {
+        self.get_remaining(player_turn, now) == 0
+    }
+
+    /// Charge the time of the player that just moved, give the increment back, and start the rival's time.
+    pub fn punch(&mut self, player_turn: bool, now: u64) {
+        let spent: u64 = now.saturating_sub(self.turn_started_at.0);
+        let increment: u64 = (self.time_control.increment_seconds as u64) * NANOSECONDS_PER_SECOND;
+
+        let bonus: u64 = match self.time_control.mode {
+            ClockMode::Fischer => increment,
+            ClockMode::Bronstein => spent.min(increment),
+        };
+
+        let remaining: &mut U64 = match player_turn {
+            false => &mut self.white_remaining,
+            true => &mut self.black_remaining,
+        };
+        remaining.0 = remaining.0.saturating_sub(spent).saturating_add(bonus);
+
+        self.turn_started_at = U64(now);
+    }
+
+    /// Same clock as at the start of a game, with white's time starting at now.
+    pub fn restart(&mut self, now: u64) {
+        *self = Clock::new(self.time_control, now);
+    }
+
+    /// The clock as seen at now, with the time of the player to move already charged.
+    pub fn get_clock_at(&self, player_turn: bool, now: u64) -> Clock {
+        let mut clock: Clock = self.clone();
+
+        let remaining: u64 = self.get_remaining(player_turn, now);
+        match player_turn {
+            false => clock.white_remaining = U64(remaining),
+            true => clock.black_remaining = U64(remaining),
+        }
+        clock.turn_started_at = U64(now.max(self.turn_started_at.0));
+
+        clock
+    }
+}
@@ -0,0 +1,88 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+
+// Block timestamps are in nanoseconds.
+const NANOSECONDS_PER_DAY: u64 = 86_400 * 1_000_000_000;
+/// Most days a game can give for each move, and as vacation days to each player.
+pub const MAX_DAYS: u32 = 365;
+
+
+/// Deadline for the player to move in a correspondence game.
+/// Each move must be done within days_per_move. Vacation days push the deadline further, a few times per game.
+#[derive(Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Correspondence{
+    days_per_move: u32,
+    // Vacation days each player starts with.
+    vacation_days: u32,
+    white_vacation_left: u32,
+    black_vacation_left: u32,
+    deadline: U64,
+}
+
+impl Correspondence{
+    /// White's first move is due days_per_move after now.
+    pub fn new(days_per_move: u32, vacation_days: u32, now: u64) -> Self {
+        Correspondence{
+            days_per_move,
+            vacation_days,
+            white_vacation_left: vacation_days,
+            black_vacation_left: vacation_days,
+            deadline: U64(now.saturating_add(days_to_nanoseconds(days_per_move))),
+        }
+    }
+
+    pub fn get_days_per_move(&self) -> u32 {
+        self.days_per_move
+    }
+
+    /// Vacation days the player (false: White, true: Black) can still take.
+    pub fn get_vacation_left(&self, player_turn: bool) -> u32 {
+        match player_turn {
+            false => self.white_vacation_left,
+            true => self.black_vacation_left,
+        }
+    }
+
+    /// Block timestamp when the time of the player to move runs out.
+    pub fn get_deadline(&self) -> U64 {
+        self.deadline
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.deadline.0
+    }
+
+    /// A move was done at now, the next one is due days_per_move later.
+    pub fn next_turn(&mut self, now: u64) {
+        self.deadline = U64(now.saturating_add(days_to_nanoseconds(self.days_per_move)));
+    }
+
+    /// Push the deadline of the player to move by days, if they have that many vacation days left.
+    pub fn take_vacation(&mut self, player_turn: bool, days: u32) -> bool {
+        let vacation_left: &mut u32 = match player_turn {
+            false => &mut self.white_vacation_left,
+            true => &mut self.black_vacation_left,
+        };
+
+        if days > *vacation_left {
+            return false;
+        }
+
+        *vacation_left -= days;
+        self.deadline = U64(self.deadline.0.saturating_add(days_to_nanoseconds(days)));
+
+        true
+    }
+
+    /// Same deadlines and vacation days as at the start of a game created at now.
+    pub fn restart(&mut self, now: u64) {
+        *self = Correspondence::new(self.days_per_move, self.vacation_days, now);
+    }
+}
+
+fn days_to_nanoseconds(days: u32) -> u64 {
+    (days as u64).saturating_mul(NANOSECONDS_PER_DAY)
+}
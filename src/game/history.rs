@@ -0,0 +1,88 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::{
+    movement::{
+        report::Report,
+        step::Step,
+    },
+    notation::san,
+    pieces::{
+        piece_ext::PieceExt,
+        promotion::Promotion,
+    },
+};
+
+
+/// A single move, as it was committed to the board.
+/// Positions are indexes (row * 8 + col) and pieces are piece numbers, same as the board squares.
+#[derive(Clone, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveRecord{
+    from: u8,
+    to: u8,
+    // The piece that moved. A promoted pawn is still a pawn here.
+    piece: u8,
+    // The piece taken with this move, 0 if none. For en passant, the captured pawn.
+    captured: u8,
+    promotion: Option<Promotion>,
+    // The rival was left under check, or checkmate.
+    check: bool,
+    checkmate: bool,
+    // The move in Standard Algebraic Notation, like "Nf3", "exd5" or "O-O".
+    san: String,
+}
+
+impl MoveRecord{
+    /// san is the notation from san::get_san, the check suffix is added here.
+    pub fn new(step: &Step, san: &str, check: bool, checkmate: bool) -> Self {
+        MoveRecord{
+            from: Report::col_row_to_index(
+                step.current_piece.get_column(),    // col: u8,
+                step.current_piece.get_row(),       // row: u8,
+            ),
+            to: Report::col_row_to_index(
+                step.target_piece.get_column(),     // col: u8,
+                step.target_piece.get_row(),        // row: u8,
+            ),
+            piece: step.current_piece.get_piece_number(),
+            captured: step.get_captured_piece_number(),
+            promotion: step.promotion,
+            check,
+            checkmate,
+            san: format!("{}{}", san, san::get_check_suffix(check, checkmate)),
+        }
+    }
+
+    pub fn get_from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn get_to(&self) -> u8 {
+        self.to
+    }
+
+    pub fn get_piece(&self) -> u8 {
+        self.piece
+    }
+
+    pub fn get_captured(&self) -> u8 {
+        self.captured
+    }
+
+    pub fn get_promotion(&self) -> Option<Promotion> {
+        self.promotion
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.check
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.checkmate
+    }
+
+    pub fn get_san(&self) -> String {
+        self.san.clone()
+    }
+}
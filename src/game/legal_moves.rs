@@ -0,0 +1,99 @@
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::{
+    movement::{
+        report::Report,
+        step::Step,
+    },
+    notation::{
+        san,
+        uci,
+    },
+    pieces::{
+        piece_ext::PieceExt,
+        promotion::Promotion,
+    },
+};
+
+
+/// A move the current player can do right now.
+/// Positions are indexes (row * 8 + col), same as move_to_index.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LegalMove{
+    from: u8,
+    to: u8,
+    // Same text accepted by move_san and move_uci.
+    san: String,
+    uci: String,
+    promotion: Option<Promotion>,
+    capture: bool,
+    castling: bool,
+    en_passant: bool,
+    // The rival would be under check, or checkmate, after it.
+    check: bool,
+    checkmate: bool,
+}
+
+impl LegalMove{
+    /// san is the notation from san::get_san, the check suffix is added here.
+    pub fn new(step: &Step, san: &str, check: bool, checkmate: bool) -> Self {
+        let from: u8 = Report::col_row_to_index(
+            step.current_piece.get_column(),    // col: u8,
+            step.current_piece.get_row(),       // row: u8,
+        );
+        let to: u8 = Report::col_row_to_index(
+            step.target_piece.get_column(),     // col: u8,
+            step.target_piece.get_row(),        // row: u8,
+        );
+
+        LegalMove{
+            from,
+            to,
+            san: format!("{}{}", san, san::get_check_suffix(check, checkmate)),
+            uci: uci::get_uci(from, to, &step.promotion),
+            promotion: step.promotion,
+            capture: step.is_capture(),
+            castling: step.is_castling(),
+            en_passant: step.is_en_passant(),
+            check,
+            checkmate,
+        }
+    }
+
+    pub fn get_from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn get_to(&self) -> u8 {
+        self.to
+    }
+
+    pub fn get_san(&self) -> String {
+        self.san.clone()
+    }
+
+    pub fn get_uci(&self) -> String {
+        self.uci.clone()
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.capture
+    }
+
+    pub fn is_castling(&self) -> bool {
+        self.castling
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.en_passant
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.check
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.checkmate
+    }
+}
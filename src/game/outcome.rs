@@ -0,0 +1,68 @@
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        history::MoveRecord,
+        result::GameResult,
+    },
+    player::errors::ErrorResponse,
+};
+
+
+/// What happened after a move request.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MoveOutcome{
+    // The move was done. record has its notation and if it left the rival under check or checkmate.
+    // result tells if the game ended with it, by checkmate or a draw.
+    Moved{
+        record: MoveRecord,
+        result: GameResult,
+    },
+    // Nothing changed in the game. reason is the message for error.
+    Rejected{
+        error: ErrorResponse,
+        reason: String,
+    },
+}
+
+impl MoveOutcome{
+    pub fn rejected(error: ErrorResponse) -> Self {
+        let reason: String = String::from(error.get_message());
+
+        MoveOutcome::Rejected{
+            error,
+            reason,
+        }
+    }
+
+    /// Same as rejected, with a reason more precise than the message of error.
+    pub fn rejected_with_reason(error: ErrorResponse, reason: &str) -> Self {
+        MoveOutcome::Rejected{
+            error,
+            reason: String::from(reason),
+        }
+    }
+
+    pub fn is_moved(&self) -> bool {
+        match self {
+            MoveOutcome::Moved{ .. } => true,
+            MoveOutcome::Rejected{ .. } => false,
+        }
+    }
+
+    pub fn get_error(&self) -> Option<ErrorResponse> {
+        match self {
+            MoveOutcome::Moved{ .. } => None,
+            MoveOutcome::Rejected{ error, .. } => Some(error.clone()),
+        }
+    }
+
+    /// Text for the logs, the move in SAN or why it was rejected.
+    pub fn get_message(&self) -> String {
+        match self {
+            MoveOutcome::Moved{ record, .. } => format!("Played {}.", record.get_san()),
+            MoveOutcome::Rejected{ reason, .. } => reason.clone(),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+
+/// How a game ended, or Running if it didn't end yet.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameResult{
+    #[default]
+    Running,
+    WhiteWins(WinReason),
+    BlackWins(WinReason),
+    Draw(DrawReason),
+    // Called off by a player before both had moved. Nobody wins.
+    Aborted,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum WinReason{
+    // The loser is under check with no move left to avoid it.
+    Checkmate,
+    // The loser gave up.
+    Resignation,
+    // The loser ran out of time.
+    Timeout,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum DrawReason{
+    // The player to move is not under check, but has no valid move left.
+    Stalemate,
+    // The game reached 255 turns.
+    TurnLimit,
+    // Claimed by a player after 50 moves each without any capture or pawn move.
+    FiftyMoveRule,
+    // After 75 moves each without any capture or pawn move.
+    SeventyFiveMoveRule,
+    // Claimed by a player when the same position happens for the third time.
+    ThreefoldRepetition,
+    // The same position happened for the fifth time.
+    FivefoldRepetition,
+    // Neither player has enough pieces left to checkmate.
+    InsufficientMaterial,
+    // A player accepted the draw offered by the other.
+    Agreement,
+    // A player ran out of time, but the rival doesn't have enough pieces to checkmate.
+    TimeoutVsInsufficientMaterial,
+}
+
+impl GameResult{
+    pub fn is_game_over(&self) -> bool {
+        *self != GameResult::Running
+    }
+
+    pub fn get_description(&self) -> String {
+        let winner: &str = match self {
+            GameResult::Running => return String::from("Running"),
+            GameResult::Aborted => return String::from("Aborted"),
+            GameResult::WhiteWins(_) => "White wins",
+            GameResult::BlackWins(_) => "Black wins",
+            GameResult::Draw(_) => "Draw",
+        };
+
+        let reason: &str = match self {
+            GameResult::WhiteWins(reason) | GameResult::BlackWins(reason) => match reason {
+                WinReason::Checkmate => "checkmate",
+                WinReason::Resignation => "resignation",
+                WinReason::Timeout => "timeout",
+            },
+            GameResult::Draw(reason) => match reason {
+                DrawReason::Stalemate => "stalemate",
+                DrawReason::TurnLimit => "turn limit",
+                DrawReason::FiftyMoveRule => "fifty-move rule",
+                DrawReason::SeventyFiveMoveRule => "seventy-five-move rule",
+                DrawReason::ThreefoldRepetition => "threefold repetition",
+                DrawReason::FivefoldRepetition => "fivefold repetition",
+                DrawReason::InsufficientMaterial => "insufficient material",
+                DrawReason::Agreement => "agreement",
+                DrawReason::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+            },
+            GameResult::Running | GameResult::Aborted => "",
+        };
+
+        format!("{} by {}", winner, reason)
+    }
+
+    /// Result as written in PGN.
+    pub fn get_pgn_result(&self) -> &str {
+        match self {
+            GameResult::Running | GameResult::Aborted => "*",
+            GameResult::WhiteWins(_) => "1-0",
+            GameResult::BlackWins(_) => "0-1",
+            GameResult::Draw(_) => "1/2-1/2",
+        }
+    }
+}
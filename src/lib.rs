@@ -1,9 +1,9 @@
-// pawn: forward 1-step                             
+// pawn: forward 1-step, diagonal captures (also en passant)
 // rook: straight line                              
 // knight: L shape                                  
 // bishop: diagonal                                 
 // queen: straight line + diagonal max distance     
-// king: straight line + diagonal 1-step can't walk into self-checkmate
+// king: straight line + diagonal 1-step can't walk into self-checkmate, or castle 2-steps towards a rook
 
 // empty:           0
 // white pawn:      1
@@ -46,14 +46,31 @@ pub mod player;
 pub mod board;
 pub mod movement;
 pub mod game;
+pub mod notation;
+pub mod events;
+pub mod challenge;
+pub mod wager;
+pub mod rating;
 
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 #[allow(unused_imports)]
-use near_sdk::{env, near_bindgen};
-use near_sdk::serde::{Deserialize, Serialize};
-
-use game::Game;
+use near_sdk::{env, near_bindgen, AccountId, PromiseOrValue, PromiseResult};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::{U64, U128};
+
+use challenge::{Challenge, ChallengeId, ColorPreference};
+use game::{Game, GameId};
+use game::clock::{Clock, TimeControl, MAX_SECONDS};
+use game::correspondence::MAX_DAYS;
+use game::history::MoveRecord;
+use game::legal_moves::LegalMove;
+use game::outcome::MoveOutcome;
+use notation::pgn;
+use pieces::promotion::Promotion;
+use player::errors::ErrorResponse;
+use rating::Rating;
+use wager::TransferMessage;
 
 near_sdk::setup_alloc!();
 
@@ -70,69 +87,781 @@ pub fn log(message: &str){
 
 
 #[near_bindgen]
-#[derive(Default, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
-#[serde(crate = "near_sdk::serde")]
+#[derive(BorshDeserialize, BorshSerialize)]
 pub struct Chess {
-    game: Game,
+    // Every game ever created, by id.
+    games: LookupMap<GameId, Game>,
+    // Id the next created game will get.
+    next_game_id: u64,
+    // Ids of the games each account plays.
+    account_games: LookupMap<AccountId, Vec<GameId>>,
+    // Challenges waiting for someone to accept them, by id.
+    challenges: UnorderedMap<ChallengeId, Challenge>,
+    // Id the next created challenge will get.
+    next_challenge_id: u64,
+    // Tokens owed, by (account, token): wagers won or given back, and payouts that failed. Sent with claim_unpaid.
+    unpaid: LookupMap<(AccountId, AccountId), u128>,
+    // Rating of each account that finished a rated game.
+    ratings: LookupMap<AccountId, Rating>,
+}
+
+impl Default for Chess{
+    fn default() -> Self{
+        Chess{
+            games: LookupMap::new(b"g".to_vec()),
+            next_game_id: 0,
+            account_games: LookupMap::new(b"a".to_vec()),
+            challenges: UnorderedMap::new(b"c".to_vec()),
+            next_challenge_id: 0,
+            unpaid: LookupMap::new(b"u".to_vec()),
+            ratings: LookupMap::new(b"r".to_vec()),
+        }
+    }
+}
+
+impl Chess{
+    // Games are stored as a whole, so every change must be written back with save_game.
+    fn load_game(&self, game_id: GameId) -> Game {
+        match self.games.get(&game_id) {
+            Some(game) => game,
+            None => panic!("Error in Chess.load_game. No game with id {}.", game_id.0),
+        }
+    }
+
+    fn save_game(&mut self, game_id: GameId, game: &Game) {
+        self.games.insert(&game_id, game);
+    }
+
+    // A new game with the caller as white against opponent.
+    fn new_game(&self, opponent: AccountId) -> Game {
+        if opponent == env::predecessor_account_id() {
+            panic!("Error in Chess.new_game. Can't play against yourself.");
+        }
+
+        let mut game: Game = Game::default();
+        if let Result::Err(message) = game.reset(opponent) {
+            panic!("Error in Chess.new_game. {}", message);
+        }
+
+        game
+    }
+
+    // Store a new game with the next id.
+    fn insert_game(&mut self, game: &Game) -> GameId {
+        let game_id: GameId = U64(self.next_game_id);
+        self.next_game_id += 1;
+        self.save_game(game_id, game);
+        self.add_account_game(&game.get_white_player(), game_id);
+        self.add_account_game(&game.get_black_player(), game_id);
+
+        events::log_game_created(game_id, game);
+        // A game from FEN or PGN might be over already.
+        if game.is_game_over() {
+            events::log_game_ended(game_id, game);
+        }
+        game_id
+    }
+
+    fn get_account_games(&self, account_id: &AccountId) -> Vec<GameId> {
+        self.account_games.get(account_id).unwrap_or_default()
+    }
+
+    fn add_account_game(&mut self, account_id: &AccountId, game_id: GameId) {
+        let mut game_ids: Vec<GameId> = self.get_account_games(account_id);
+        if !game_ids.contains(&game_id) {
+            game_ids.push(game_id);
+            self.account_games.insert(account_id, &game_ids);
+        }
+    }
+
+    fn remove_account_game(&mut self, account_id: &AccountId, game_id: GameId) {
+        let mut game_ids: Vec<GameId> = self.get_account_games(account_id);
+        game_ids.retain(|id| *id != game_id);
+        self.account_games.insert(account_id, &game_ids);
+    }
+
+    // Add amount of token_id to what account_id can get with claim_unpaid.
+    fn owe(&mut self, account_id: &AccountId, token_id: &AccountId, amount: u128) {
+        let key: (AccountId, AccountId) = (account_id.clone(), token_id.clone());
+        let unpaid: u128 = self.unpaid.get(&key).unwrap_or_default();
+        self.unpaid.insert(&key, &(unpaid + amount));
+    }
+
+    // The game just ended. Log it, update the ratings if it's rated and pay the pot, if it has one.
+    // Tokens aren't sent right away: the call ending the game doesn't need gas for it. Players get them with claim_unpaid.
+    fn end_game(&mut self, game_id: GameId, game: &mut Game) {
+        events::log_game_ended(game_id, game);
+        if game.is_rated() {
+            self.update_ratings(game_id, game);
+        }
+
+        let payouts: Vec<(AccountId, u128)> = game.take_payouts();
+        if payouts.is_empty() {
+            return;
+        }
+        self.save_game(game_id, game);
+
+        for (account_id, amount) in payouts {
+            match game.get_token() {
+                None => wager::pay(&account_id, &None, amount),
+                Some(token_id) => {
+                    self.owe(&account_id, &token_id, amount);
+                    log(&format!("{} of {} can be claimed by {} with claim_unpaid.", amount, token_id, account_id));
+                },
+            }
+        }
+    }
+
+    // Both players of a finished game get their new rating, from the ratings they had before it.
+    fn update_ratings(&mut self, game_id: GameId, game: &Game) {
+        let white_score: f64 = match rating::get_white_score(&game.get_result()) {
+            Some(score) => score,
+            None => return,
+        };
+
+        let (white, black): (AccountId, AccountId) = (game.get_white_player(), game.get_black_player());
+        let (white_before, black_before): (Rating, Rating) = (self.get_rating(white.clone()), self.get_rating(black.clone()));
+
+        let mut white_rating: Rating = white_before.clone();
+        white_rating.update(&black_before, white_score);
+        let mut black_rating: Rating = black_before.clone();
+        black_rating.update(&white_before, 1.0 - white_score);
+
+        self.ratings.insert(&white, &white_rating);
+        self.ratings.insert(&black, &black_rating);
+
+        events::log_ratings_updated(game_id, &white, &white_rating, &black, &black_rating);
+    }
+
+    // Store a new challenge with the next id. token is None for a wager in NEAR.
+    fn open_challenge(
+        &mut self,
+        opponent: Option<AccountId>,
+        color: ColorPreference,
+        time_control: Option<TimeControl>,
+        rated: bool,
+        wager: u128,
+        token: Option<AccountId>,
+    ) -> ChallengeId {
+        let challenger: AccountId = env::predecessor_account_id();
+        if opponent.as_ref() == Some(&challenger) {
+            panic!("Error in Chess.open_challenge. Can't play against yourself.");
+        }
+        if let Some(time_control) = &time_control {
+            if time_control.get_base_seconds() == 0 {
+                panic!("Error in Chess.open_challenge. Base time must be at least 1 second.");
+            }
+            if (time_control.get_base_seconds() > MAX_SECONDS) || (time_control.get_increment_seconds() > MAX_SECONDS) {
+                panic!("Error in Chess.open_challenge. Base time and increment can't be over {} seconds.", MAX_SECONDS);
+            }
+        }
+        // The pot holds both wagers.
+        if wager > u128::MAX / 2 {
+            panic!("Error in Chess.open_challenge. The wager can't be over {}.", u128::MAX / 2);
+        }
+        // Without a clock, a losing player could stop moving and the pot would never be paid.
+        if (wager > 0) && time_control.is_none() {
+            panic!("Error in Chess.open_challenge. A challenge with a wager must have a time control.");
+        }
+
+        let challenge_id: ChallengeId = U64(self.next_challenge_id);
+        self.next_challenge_id += 1;
+
+        let mut challenge: Challenge = Challenge::new(
+            challenge_id,                       // id: ChallengeId,
+            challenger,                         // challenger: AccountId,
+            opponent,                           // opponent: Option<AccountId>,
+            color,                              // color: ColorPreference,
+            time_control,                       // time_control: Option<TimeControl>,
+            env::block_timestamp(),             // created_at: u64,
+        );
+        challenge.set_rated(rated);
+        challenge.set_wager(
+            wager,                              // wager: u128,
+            token,                              // token: Option<AccountId>,
+        );
+        self.challenges.insert(&challenge_id, &challenge);
+
+        events::log_challenge_created(&challenge);
+        challenge_id
+    }
+
+    // Start the game of an accepted challenge, between the challenger and account_id, with both wagers in.
+    fn start_challenge(&mut self, challenge: &Challenge, account_id: &AccountId) -> GameId {
+        let (white, black): (AccountId, AccountId) = challenge.get_players(
+            account_id,                         // account_id: &AccountId,
+            env::random_seed()[0],              // random_byte: u8,
+        );
+
+        let mut game: Game = Game::default();
+        game.start(
+            white,                              // white: AccountId,
+            black,                              // black: AccountId,
+        );
+        if let Some(time_control) = challenge.get_time_control() {
+            game.set_time_control(time_control);
+        }
+        game.set_wager(
+            challenge.get_wager().0,            // wager: u128,
+            challenge.get_token(),              // token: Option<AccountId>,
+        );
+        game.set_rated(challenge.is_rated());
+
+        self.challenges.remove(&challenge.get_id());
+        let game_id: GameId = self.insert_game(&game);
+
+        events::log_challenge_accepted(challenge.get_id(), account_id, game_id);
+        game_id
+    }
+
+    // Save the game after a move request and log what happened.
+    // was_over is if the game was over before the request.
+    fn save_move(&mut self, game_id: GameId, game: &mut Game, was_over: bool, outcome: &MoveOutcome) {
+        self.save_game(game_id, game);
+        log(&outcome.get_message());
+
+        if let MoveOutcome::Moved{ record, .. } = outcome {
+            events::log_move_made(game_id, game, &env::predecessor_account_id(), record);
+            if record.is_check() {
+                events::log_check(game_id, game, record);
+            }
+        }
+
+        // Reaching the turn limit ends the game even if the move is rejected.
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, game);
+        }
+    }
 }
 
 #[near_bindgen]
 impl Chess{
-    // Reset the game to the beginning.
-    pub fn reset_game(&mut self) -> String {
-        self.game.reset_game_game()
+    // Start a new game. Caller plays white against opponent. Returns the id of the game.
+    pub fn create_game(&mut self, opponent: AccountId) -> GameId {
+        let game: Game = self.new_game(opponent);
+        self.insert_game(&game)
+    }
+
+    // Same as create_game, with a clock for each player. A player that runs out of time loses.
+    pub fn create_timed_game(&mut self, opponent: AccountId, time_control: TimeControl) -> GameId {
+        if time_control.get_base_seconds() == 0 {
+            panic!("Error in Chess.create_timed_game. Base time must be at least 1 second.");
+        }
+        if (time_control.get_base_seconds() > MAX_SECONDS) || (time_control.get_increment_seconds() > MAX_SECONDS) {
+            panic!("Error in Chess.create_timed_game. Base time and increment can't be over {} seconds.", MAX_SECONDS);
+        }
+
+        let mut game: Game = self.new_game(opponent);
+        game.set_time_control(time_control);
+
+        self.insert_game(&game)
+    }
+
+    // Same as create_game, for correspondence: each move must be done within days_per_move.
+    // Each player can also push a deadline with up to vacation_days over the game.
+    pub fn create_correspondence_game(&mut self, opponent: AccountId, days_per_move: u32, vacation_days: u32) -> GameId {
+        if days_per_move == 0 {
+            panic!("Error in Chess.create_correspondence_game. Must have at least 1 day per move.");
+        }
+        if (days_per_move > MAX_DAYS) || (vacation_days > MAX_DAYS) {
+            panic!("Error in Chess.create_correspondence_game. Days per move and vacation days can't be over {}.", MAX_DAYS);
+        }
+
+        let mut game: Game = self.new_game(opponent);
+        game.set_correspondence(days_per_move, vacation_days);
+
+        self.insert_game(&game)
+    }
+
+    // Same as create_game, but starting from the position in a FEN string.
+    pub fn create_game_from_fen(&mut self, opponent: AccountId, fen: String) -> GameId {
+        let mut game: Game = self.new_game(opponent);
+        if let Result::Err(err) = game.load_fen(&fen) {
+            panic!("Error in Chess.create_game_from_fen. Invalid FEN. {}", err.get_message());
+        }
+
+        self.insert_game(&game)
+    }
+
+    // Same as create_game, but playing every move of a PGN first (from its FEN tag, if any).
+    // Fails with the ply number and the reason of the first invalid move.
+    pub fn create_game_from_pgn(&mut self, opponent: AccountId, pgn: String) -> GameId {
+        let mut game: Game = self.new_game(opponent);
+        if let Result::Err(message) = game.load_pgn(&pgn) {
+            panic!("Error in Chess.create_game_from_pgn. {}", message);
+        }
+
+        self.insert_game(&game)
+    }
+
+    // Invite someone to play. Anyone can accept it, or only opponent if given. A rated game changes the ratings of both players.
+    // The attached deposit is the wager, held until the game ends. A wager needs a time control. Returns the id of the challenge.
+    #[payable]
+    pub fn create_challenge(
+        &mut self,
+        opponent: Option<AccountId>,
+        color: ColorPreference,
+        time_control: Option<TimeControl>,
+        rated: bool,
+    ) -> ChallengeId {
+        self.open_challenge(
+            opponent,                           // opponent: Option<AccountId>,
+            color,                              // color: ColorPreference,
+            time_control,                       // time_control: Option<TimeControl>,
+            rated,                              // rated: bool,
+            env::attached_deposit(),            // wager: u128,
+            None,                               // token: Option<AccountId>,
+        )
+    }
+
+    // Same as create_challenge, with a wager in the NEP-141 token of token_id.
+    // The challenger puts it in with ft_transfer_call, the msg being {"challenge_id": "<id>"}. Until then nobody can accept it.
+    pub fn create_token_challenge(
+        &mut self,
+        opponent: Option<AccountId>,
+        color: ColorPreference,
+        time_control: Option<TimeControl>,
+        rated: bool,
+        token_id: AccountId,
+        wager: U128,
+    ) -> ChallengeId {
+        if wager.0 == 0 {
+            panic!("Error in Chess.create_token_challenge. The wager can't be 0.");
+        }
+
+        self.open_challenge(
+            opponent,                           // opponent: Option<AccountId>,
+            color,                              // color: ColorPreference,
+            time_control,                       // time_control: Option<TimeControl>,
+            rated,                              // rated: bool,
+            wager.0,                            // wager: u128,
+            Some(token_id),                     // token: Option<AccountId>,
+        )
+    }
+
+    // Up to "limit" open challenges, starting from "from_index". Includes the ones for a specific opponent.
+    pub fn list_open_challenges(&self, from_index: u64, limit: u64) -> Vec<Challenge> {
+        self.challenges
+            .values_as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    // Accept a challenge, starting a new game between the challenger and the caller. Returns the id of the game.
+    // The attached deposit must match the wager of the challenge. The winner gets both.
+    #[payable]
+    pub fn accept_challenge(&mut self, challenge_id: ChallengeId) -> GameId {
+        let challenge: Challenge = match self.challenges.get(&challenge_id) {
+            Some(challenge) => challenge,
+            None => panic!("Error in Chess.accept_challenge. No open challenge with id {}.", challenge_id.0),
+        };
+
+        if challenge.get_token().is_some() {
+            panic!("Error in Chess.accept_challenge. The wager is in a token, accept it with ft_transfer_call.");
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if !challenge.can_accept(&account_id) {
+            panic!("Error in Chess.accept_challenge. This challenge can't be accepted by {}.", account_id);
+        }
+
+        let wager: u128 = challenge.get_wager().0;
+        if env::attached_deposit() != wager {
+            panic!("Error in Chess.accept_challenge. Must attach the wager of the challenge ({} yoctoNEAR).", wager);
+        }
+
+        self.start_challenge(&challenge, &account_id)
+    }
+
+    // NEP-141 receiver. Tokens sent with ft_transfer_call are the wager of the challenge in msg, {"challenge_id": "<id>"}.
+    // From the challenger, they fund the challenge. From anyone else, they accept it and start the game.
+    // Panics if the tokens can't be used, so the token contract gives them back.
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let challenge_id: ChallengeId = match TransferMessage::parse(&msg) {
+            Some(message) => message.challenge_id,
+            None => panic!("Error in Chess.ft_on_transfer. msg must be {{\"challenge_id\": \"<id>\"}}."),
+        };
+
+        let mut challenge: Challenge = match self.challenges.get(&challenge_id) {
+            Some(challenge) => challenge,
+            None => panic!("Error in Chess.ft_on_transfer. No open challenge with id {}.", challenge_id.0),
+        };
+
+        if challenge.get_token() != Some(env::predecessor_account_id()) {
+            panic!("Error in Chess.ft_on_transfer. The wager of this challenge isn't in this token.");
+        }
+        if amount != challenge.get_wager() {
+            panic!("Error in Chess.ft_on_transfer. Must send the wager of the challenge ({}).", challenge.get_wager().0);
+        }
+
+        if sender_id == challenge.get_challenger() {
+            if challenge.is_funded() {
+                panic!("Error in Chess.ft_on_transfer. The wager of this challenge is already in.");
+            }
+
+            challenge.fund();
+            self.challenges.insert(&challenge_id, &challenge);
+            log(&format!("Challenge {} can be accepted now.", challenge_id.0));
+        } else {
+            if !challenge.can_accept(&sender_id) {
+                panic!("Error in Chess.ft_on_transfer. This challenge can't be accepted by {}.", sender_id);
+            }
+
+            self.start_challenge(&challenge, &sender_id);
+        }
+
+        // Every token sent is used.
+        PromiseOrValue::Value(U128(0))
+    }
+
+    // Callback of a token payout. If the transfer failed, the account can get it with claim_unpaid.
+    #[private]
+    pub fn on_token_payout(&mut self, account_id: AccountId, token_id: AccountId, amount: U128) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        self.owe(&account_id, &token_id, amount.0);
+
+        log(&format!("Couldn't send {} of {} to {}, it can be claimed with claim_unpaid.", amount.0, token_id, account_id));
+    }
+
+    // Elo rating of account_id and how many rated games it finished. 1500 for an account without any.
+    pub fn get_rating(&self, account_id: AccountId) -> Rating {
+        self.ratings.get(&account_id).unwrap_or_default()
+    }
+
+    // Tokens of token_id owed to account_id: wagers won or given back, and payouts that failed.
+    pub fn get_unpaid(&self, account_id: AccountId, token_id: AccountId) -> U128 {
+        U128(self.unpaid.get(&(account_id, token_id)).unwrap_or_default())
+    }
+
+    // Send the tokens of token_id owed to the caller. Returns how many.
+    // Needs 30 Tgas attached, for the token transfer and its callback.
+    pub fn claim_unpaid(&mut self, token_id: AccountId) -> U128 {
+        if env::prepaid_gas() < wager::GAS_FOR_CLAIM_UNPAID {
+            panic!("Error in Chess.claim_unpaid. Attach at least {} gas, for the token transfer and its callback.", wager::GAS_FOR_CLAIM_UNPAID);
+        }
+
+        let account_id: AccountId = env::predecessor_account_id();
+        let amount: u128 = match self.unpaid.remove(&(account_id.clone(), token_id.clone())) {
+            Some(amount) => amount,
+            None => panic!("Error in Chess.claim_unpaid. Nothing of {} owed to {}.", token_id, account_id),
+        };
+
+        wager::pay(&account_id, &Some(token_id), amount);
+        U128(amount)
+    }
+
+    // Take back an open challenge, and its wager. Only the challenger can do it. A token wager is claimed with claim_unpaid.
+    pub fn cancel_challenge(&mut self, challenge_id: ChallengeId) {
+        let challenge: Challenge = match self.challenges.get(&challenge_id) {
+            Some(challenge) => challenge,
+            None => panic!("Error in Chess.cancel_challenge. No open challenge with id {}.", challenge_id.0),
+        };
+
+        let account_id: AccountId = env::predecessor_account_id();
+        if challenge.get_challenger() != account_id {
+            panic!("Error in Chess.cancel_challenge. Only the challenger can cancel it.");
+        }
+
+        self.challenges.remove(&challenge_id);
+
+        if challenge.is_funded() {
+            match challenge.get_token() {
+                None => wager::pay(&account_id, &None, challenge.get_wager().0),
+                Some(token_id) => self.owe(&account_id, &token_id, challenge.get_wager().0),
+            }
+        }
+
+        events::log_challenge_cancelled(challenge_id, &account_id);
+    }
+
+    // Reset the game to the beginning. Caller plays white against opponent. Only the players of the game can do it.
+    pub fn reset_game(&mut self, game_id: GameId, opponent: AccountId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let previous_players: [AccountId; 2] = [game.get_white_player(), game.get_black_player()];
+        let response: String = match game.reset(opponent) {
+            Result::Err(message) => message,
+            Result::Ok(()) => {
+                self.save_game(game_id, &game);
+
+                // The players might have changed.
+                for account_id in previous_players.iter() {
+                    self.remove_account_game(account_id, game_id);
+                }
+                self.add_account_game(&game.get_white_player(), game_id);
+                self.add_account_game(&game.get_black_player(), game_id);
+                events::log_game_reset(game_id, &game);
+
+                String::from("Reset successful.")
+            },
+        };
+
+        log(&response);
+        response
     }
 
     // Return the state of the game.
-    pub fn get_game_status(&self) -> Game{
-        self.game.get_game_status_game()
+    pub fn get_game_status(&self, game_id: GameId) -> Game{
+        self.load_game(game_id).get_game_status_game()
     }
 
     // Get the name of a piece in given position.
-    pub fn get_piece_name(&self, col: u8, row: u8) -> String{
-        self.game.get_piece_name_game(col, row)
+    pub fn get_piece_name(&self, game_id: GameId, col: u8, row: u8) -> String{
+        self.load_game(game_id).get_piece_name_game(col, row)
     }
 
     // Get the name of a piece in given index.
-    pub fn get_piece_name_index(&self, index: u8) -> String {
-        self.game.get_piece_name_index_game(index)
+    pub fn get_piece_name_index(&self, game_id: GameId, index: u8) -> String {
+        self.load_game(game_id).get_piece_name_index_game(index)
+    }
+
+    // Get the current position as a FEN string.
+    pub fn get_fen(&self, game_id: GameId) -> String {
+        self.load_game(game_id).get_fen_game()
+    }
+
+    // Get the whole game in PGN, to use with other chess tools.
+    pub fn get_pgn(&self, game_id: GameId) -> String {
+        pgn::get_pgn(
+            &self.load_game(game_id),   // game: &Game,
+            game_id,                    // game_id: GameId,
+            &env::current_account_id(), // site: &str,
+        )
     }
 
     // Get the entire board as a string.
-    pub fn get_board(&self) -> String {
-        self.game.get_board_game()
+    pub fn get_board(&self, game_id: GameId) -> String {
+        self.load_game(game_id).get_board_game()
+    }
+
+    // Number of moves done in the game so far.
+    pub fn get_history_length(&self, game_id: GameId) -> u64 {
+        self.load_game(game_id).get_history_length_game()
+    }
+
+    // Up to "limit" moves of the game, starting from move number "from_index" (0 is the first move).
+    pub fn get_history(&self, game_id: GameId, from_index: u64, limit: u64) -> Vec<MoveRecord> {
+        self.load_game(game_id).get_history_game(from_index, limit)
+    }
+
+    // Every move the current player can do, with SAN and UCI notation. Empty if the game is over.
+    pub fn get_legal_moves(&self, game_id: GameId) -> Vec<LegalMove> {
+        self.load_game(game_id).get_legal_moves_game()
+    }
+
+    // Same as get_legal_moves, only for the piece in square, like "e2". Empty for a square that doesn't exist.
+    pub fn get_legal_moves_from(&self, game_id: GameId, square: String) -> Vec<LegalMove> {
+        self.load_game(game_id).get_legal_moves_from_game(square)
+    }
+
+    // End the game as a draw, if the rules allow it to be claimed.
+    pub fn claim_draw(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let response: String = game.claim_draw_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, &mut game);
+        }
+        response
     }
 
     
+    // Win the game if the rival ran out of time. A draw if the caller can't checkmate with the pieces left, whatever the rival does.
+    pub fn claim_timeout(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let response: String = game.claim_timeout_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, &mut game);
+        }
+        response
+    }
+
+    // Push the deadline of the current move by days, in a correspondence game. Only for the player to move.
+    pub fn take_vacation(&mut self, game_id: GameId, days: u32) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let response: String = game.take_vacation_game(days);
+        self.save_game(game_id, &game);
+
+        log(&response);
+        response
+    }
+
+    // Number of games account_id plays or played, to page through get_games_to_move.
+    pub fn get_account_games_length(&self, account_id: AccountId) -> u64 {
+        self.get_account_games(&account_id).len() as u64
+    }
+
+    // Ids of the running games where account_id is the player to move, among up to "limit" games of the account
+    // starting from "from_index". A page can have fewer than limit, or none, and the next one still have some.
+    pub fn get_games_to_move(&self, account_id: AccountId, from_index: u64, limit: u64) -> Vec<GameId> {
+        self.get_account_games(&account_id)
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter(|game_id| {
+                let game: Game = self.load_game(*game_id);
+
+                !game.is_game_over() && (game.get_player_account() == account_id)
+            })
+            .collect()
+    }
+
+    // Remaining time of each player (in nanoseconds) right now. None if the game has no clock.
+    pub fn get_clock(&self, game_id: GameId) -> Option<Clock> {
+        self.load_game(game_id).get_clock_game()
+    }
+
+    // Give up the game. The rival wins.
+    pub fn resign(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let response: String = game.resign_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, &mut game);
+        }
+        response
+    }
+
+    // Call the game off, before both players moved. Each wager is given back.
+    pub fn abort_game(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let response: String = game.abort_game_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, &mut game);
+        }
+        response
+    }
+
+    // Offer a draw to the rival. It's taken back when the caller moves.
+    pub fn offer_draw(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let had_offer: bool = game.get_draw_offer().is_some();
+        let response: String = game.offer_draw_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !had_offer && game.get_draw_offer().is_some() {
+            events::log_draw_offered(game_id, &env::predecessor_account_id());
+        }
+        response
+    }
+
+    // Accept the draw offered by the rival, ending the game.
+    pub fn accept_draw(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let response: String = game.accept_draw_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if !was_over && game.is_game_over() {
+            self.end_game(game_id, &mut game);
+        }
+        response
+    }
+
+    // Decline the draw offered by the rival.
+    pub fn decline_draw(&mut self, game_id: GameId) -> String {
+        let mut game: Game = self.load_game(game_id);
+        let had_offer: bool = game.get_draw_offer().is_some();
+        let response: String = game.decline_draw_game();
+        self.save_game(game_id, &game);
+
+        log(&response);
+        if had_offer && game.get_draw_offer().is_none() {
+            events::log_draw_declined(game_id, &env::predecessor_account_id());
+        }
+        response
+    }
+
     // Move a piece from position "current" to "target" by column and row.
+    // promotion is only given when a pawn reaches the last row.
     pub fn move_to(
         &mut self,
+        game_id: GameId,
         current_col: u8,
         current_row: u8,
         target_col: u8,
         target_row: u8,
-    ) -> String {
+        promotion: Option<Promotion>,
+    ) -> MoveOutcome {
+        if (current_col > 7) || (current_row > 7) || (target_col > 7) || (target_row > 7) {
+            let outcome: MoveOutcome = MoveOutcome::rejected_with_reason(
+                ErrorResponse::OutOfRange,      // error: ErrorResponse,
+                "Invalid position. Column and row must be from 0 to 7.",// reason: &str,
+            );
+            log(&outcome.get_message());
+            return outcome;
+        }
+
         let current_index: u8 = current_row * 8 + current_col;
         let target_index: u8 = target_row * 8 + target_col;
         return self.move_to_index(
+            game_id,                    // game_id: GameId,
             current_index,              // current: u8,
             target_index,               // target: u8,
+            promotion,                  // promotion: Option<Promotion>,
         );
     }
 
+    // Move a piece with Standard Algebraic Notation, like "Nf3", "exd5", "O-O" or "e8=Q".
+    // Check (+), checkmate (#) and annotations (!, ?) can be added, but aren't needed.
+    pub fn move_san(&mut self, game_id: GameId, san: String) -> MoveOutcome {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let outcome: MoveOutcome = game.move_san_game(san);
+        self.save_move(game_id, &mut game, was_over, &outcome);
+
+        outcome
+    }
+
+    // Move a piece with UCI notation, like "e2e4", "e1g1" (castling) or "e7e8q" (promotion).
+    pub fn move_uci(&mut self, game_id: GameId, uci: String) -> MoveOutcome {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let outcome: MoveOutcome = game.move_uci_game(uci);
+        self.save_move(game_id, &mut game, was_over, &outcome);
+
+        outcome
+    }
+
     // Move a piece from position "current" to "target" by index.
+    // Every move function returns the move done, or a typed error with why it was rejected.
     pub fn move_to_index(
         &mut self,
+        game_id: GameId,
         current: u8,
         target: u8,
-    ) -> String {
-        let response: String = self.game.move_to_game(
+        promotion: Option<Promotion>,
+    ) -> MoveOutcome {
+        let mut game: Game = self.load_game(game_id);
+        let was_over: bool = game.is_game_over();
+        let outcome: MoveOutcome = game.move_to_game(
             current,
             target,
+            promotion,
         );
+        self.save_move(game_id, &mut game, was_over, &outcome);
 
-        log(&response);
-        response
+        outcome
     }
 }
 
@@ -141,11 +870,28 @@ impl Chess{
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::GameId;
     // use near_sdk::test_utils::VMContextBuilder;
-    use near_sdk::{testing_env, VMContext, MockedBlockchain};
+    use near_sdk::{test_utils, testing_env, VMContext, MockedBlockchain};
     use crate::board::Board;
+    use crate::game::clock::ClockMode;
+    use crate::game::correspondence::Correspondence;
+    use crate::game::result::{DrawReason, GameResult, WinReason};
+    use crate::movement::step::Step;
+    use crate::notation::fen::{self, FenError};
+    use crate::player::players::Players;
+
+    // Accounts playing each color in the tests.
+    const WHITE: &str = "jane.testnet";
+    const BLACK: &str = "bob.testnet";
+    // Id of the game created by new_contract.
+    const GAME: GameId = U64(0);
 
     fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
+        get_account_context(WHITE, input, is_view)
+    }
+
+    fn get_account_context(account_id: &str, input: Vec<u8>, is_view: bool) -> VMContext {
         // VMContextBuilder::new()
         //     .signer_account_id("tester_near".to_string())
         //     .is_view(is_view)
@@ -155,13 +901,16 @@ mod tests {
             current_account_id: "alice.testnet".to_string(),
             signer_account_id: "robert.testnet".to_string(),
             signer_account_pk: vec![0, 1, 2],
-            predecessor_account_id: "jane.testnet".to_string(),
+            predecessor_account_id: account_id.to_string(),
             input,
             block_index: 0,
             block_timestamp: 0,
-            account_balance: 0,
+            // Enough for the 1 yoctoNEAR attached to every ft_transfer payout.
+            account_balance: 10u128.pow(24),
             account_locked_balance: 0,
-            storage_usage: 0,
+            // testing_env! keeps the storage but not its usage. Overwriting a key with less
+            // usage than it had would underflow, so every context starts with plenty.
+            storage_usage: 10u64.pow(6),
             attached_deposit: 0,
             prepaid_gas: 10u64.pow(18),
             random_seed: vec![0, 1, 2],
@@ -171,6 +920,43 @@ mod tests {
         }
     }
 
+    // Context for account_id, seconds after the start of the chain.
+    fn get_time_context(account_id: &str, seconds: u64) -> VMContext {
+        let mut context: VMContext = get_account_context(account_id, vec![], false);
+        context.block_timestamp = seconds * 1_000_000_000;
+
+        context
+    }
+
+    // Context for account_id, attaching deposit yoctoNEAR.
+    fn get_deposit_context(account_id: &str, deposit: u128) -> VMContext {
+        let mut context: VMContext = get_account_context(account_id, vec![], false);
+        context.attached_deposit = deposit;
+
+        context
+    }
+
+    // Context for the account playing the color that moves next.
+    fn get_turn_context(contract: &Chess) -> VMContext {
+        let account_id: &str = match contract.load_game(GAME).get_player_turn() {
+            false => WHITE,
+            true => BLACK,
+        };
+
+        get_account_context(account_id, vec![], false)
+    }
+
+    // A contract with a single game, between WHITE and BLACK.
+    fn new_contract() -> Chess {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Chess::default();
+        assert_eq!(contract.create_game(BLACK.to_string()), GAME);
+
+        contract
+    }
+
     fn board_equals(board: Vec<u8>, other: [u8; 64]) -> bool {
         assert_eq!(board.len(), 64, "Board Length is {}.", board.len());
         for index in 0..board.len() {
@@ -194,24 +980,27 @@ mod tests {
         counter: u8,
         squares: [u8; 64]
     ) {
-        let context = get_context(vec![], false);
+        let context = get_turn_context(contract);
         testing_env!(context);
 
         println!("Step: {}", counter);
         println!("{}",
             &contract
                 .move_to(
+                    GAME,
                     start_col, 
                     start_row, 
                     end_col,
                     end_row,
-                )[..]
+                    None,
+                )
+                .get_message()
         );
 
         let context = get_context(vec![], true);
         testing_env!(context);
 
-        let game_status: Game = contract.get_game_status();
+        let game_status: Game = contract.get_game_status(GAME);
 
         let board: Vec<u8> = game_status
             .get_squares();
@@ -233,7 +1022,7 @@ mod tests {
         let context = get_context(vec![], true);
         testing_env!(context);
 
-        let game_status: Game = contract.get_game_status();
+        let game_status: Game = contract.get_game_status(GAME);
         let black_check: bool = game_status.is_black_check();
         let board: Vec<u8> = game_status.get_squares();
         println!("{}", Board::get_board_string(
@@ -241,6 +1030,7 @@ mod tests {
         ));
         
         assert!(black_check, "Black was supposed to be under checkmate here");
+        assert!(game_status.get_result() == GameResult::WhiteWins(WinReason::Checkmate));
     }
 
 
@@ -249,8 +1039,8 @@ mod tests {
         let context = get_context(vec![], false);
         testing_env!(context);
 
-        let mut contract = Chess::default();
-        let game: Game = contract.get_game_status();
+        let mut contract = new_contract();
+        let game: Game = contract.get_game_status(GAME);
         let board: Vec<u8> =  game.get_game_status_game()
             .get_squares();
 
@@ -1002,4 +1792,1390 @@ mod tests {
 
         
     }
-}
\ No newline at end of file
+
+    fn play_move(
+        contract: &mut Chess,
+        start_col: u8,
+        start_row: u8,
+        end_col: u8,
+        end_row: u8,
+    ) -> MoveOutcome {
+        let context = get_turn_context(contract);
+        testing_env!(context);
+
+        contract.move_to(
+            GAME,
+            start_col,
+            start_row,
+            end_col,
+            end_row,
+            None,
+        )
+    }
+
+    fn get_squares(contract: &Chess) -> Vec<u8> {
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        contract.get_game_status(GAME).get_squares()
+    }
+
+    #[test]
+    fn castling() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        play_move(&mut contract, 4, 6, 4, 4);
+        play_move(&mut contract, 4, 1, 4, 3);
+        play_move(&mut contract, 6, 7, 5, 5);
+        play_move(&mut contract, 1, 0, 2, 2);
+        play_move(&mut contract, 5, 7, 2, 4);
+        play_move(&mut contract, 6, 0, 5, 2);
+
+        // White castles king side.
+        assert!(play_move(&mut contract, 4, 7, 6, 7).is_moved());
+
+        // Black bishop is still between king and rook.
+        assert!(play_move(&mut contract, 4, 0, 6, 0).get_error() == Some(ErrorResponse::InvalidMove));
+
+        play_move(&mut contract, 5, 0, 4, 1);
+        play_move(&mut contract, 3, 6, 3, 5);
+
+        // Black castles king side.
+        assert!(play_move(&mut contract, 4, 0, 6, 0).is_moved());
+
+        assert!(board_equals(get_squares(&contract),
+            [
+                8,  0, 10, 11,  0,  8, 12,  0,
+                7,  7,  7,  7, 10,  7,  7,  7,
+                0,  0,  9,  0,  0,  9,  0,  0,
+                0,  0,  0,  0,  7,  0,  0,  0,
+                0,  0,  4,  0,  1,  0,  0,  0,
+                0,  0,  0,  1,  0,  3,  0,  0,
+                1,  1,  1,  0,  0,  1,  1,  1,
+                2,  3,  4,  5,  0,  2,  6,  0,
+            ])
+        );
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        let castling = contract.get_game_status(GAME).get_castling_rights();
+        assert!(!castling.can_castle(&pieces::piece::Color::WHITE, false));
+        assert!(!castling.can_castle(&pieces::piece::Color::BLACK, false));
+    }
+
+    #[test]
+    fn en_passant() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        play_move(&mut contract, 4, 6, 4, 4);
+        play_move(&mut contract, 0, 1, 0, 2);
+        play_move(&mut contract, 4, 4, 4, 3);
+        play_move(&mut contract, 3, 1, 3, 3);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        // Black pawn skipped position (3, 2).
+        assert_eq!(contract.get_game_status(GAME).get_en_passant(), Some(19));
+
+        // White pawn captures the black pawn beside it.
+        assert!(play_move(&mut contract, 4, 3, 3, 2).is_moved());
+
+        assert!(board_equals(get_squares(&contract),
+            [
+                8, 9, 10, 11, 12, 10, 9, 8,
+                0, 7,  7,  0,  7,  7, 7, 7,
+                7, 0,  0,  1,  0,  0, 0, 0,
+                0, 0,  0,  0,  0,  0, 0, 0,
+                0, 0,  0,  0,  0,  0, 0, 0,
+                0, 0,  0,  0,  0,  0, 0, 0,
+                1, 1,  1,  1,  0,  1, 1, 1,
+                2, 3,  4,  5,  6,  4, 3, 2,
+            ])
+        );
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        assert_eq!(contract.get_game_status(GAME).get_en_passant(), None);
+    }
+
+    #[test]
+    fn promotion() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        play_move(&mut contract, 1, 6, 1, 4);
+        play_move(&mut contract, 0, 1, 0, 3);
+        play_move(&mut contract, 1, 4, 0, 3);
+        play_move(&mut contract, 1, 1, 1, 2);
+        play_move(&mut contract, 0, 3, 1, 2);
+        play_move(&mut contract, 2, 0, 0, 2);
+        play_move(&mut contract, 1, 2, 2, 1);
+
+        // Promotion is only valid for a pawn reaching the last row.
+        let context = get_turn_context(&contract);
+        testing_env!(context);
+        assert!(contract.move_to(GAME, 6, 0, 5, 2, Some(Promotion::QUEEN)).get_error() == Some(ErrorResponse::InvalidPromotion));
+
+        play_move(&mut contract, 6, 0, 5, 2);
+
+        // The pawn can't reach the last row without choosing a promotion.
+        assert!(play_move(&mut contract, 2, 1, 1, 0).get_error() == Some(ErrorResponse::InvalidPromotion));
+
+        let context = get_turn_context(&contract);
+        testing_env!(context);
+        assert!(contract.move_to(GAME, 2, 1, 1, 0, Some(Promotion::QUEEN)).is_moved());
+
+        assert!(board_equals(get_squares(&contract),
+            [
+                 8, 5,  0, 11, 12, 10, 0, 8,
+                 0, 0,  0,  7,  7,  7, 7, 7,
+                10, 0,  0,  0,  0,  9, 0, 0,
+                 0, 0,  0,  0,  0,  0, 0, 0,
+                 0, 0,  0,  0,  0,  0, 0, 0,
+                 0, 0,  0,  0,  0,  0, 0, 0,
+                 1, 0,  1,  1,  1,  1, 1, 1,
+                 2, 3,  4,  5,  6,  4, 3, 2,
+            ])
+        );
+    }
+
+    #[test]
+    fn stalemate() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        // Shortest known stalemate (Sam Loyd).
+        let moves: [(u8, u8, u8, u8); 19] = [
+            (4, 6, 4, 5), (0, 1, 0, 3),
+            (3, 7, 7, 3), (0, 0, 0, 2),
+            (7, 3, 0, 3), (7, 1, 7, 3),
+            (7, 6, 7, 4), (0, 2, 7, 2),
+            (0, 3, 2, 1), (5, 1, 5, 2),
+            (2, 1, 3, 1), (4, 0, 5, 1),
+            (3, 1, 1, 1), (3, 0, 3, 5),
+            (1, 1, 1, 0), (3, 5, 7, 1),
+            (1, 0, 2, 0), (5, 1, 6, 2),
+            (2, 0, 4, 2),
+        ];
+
+        for (start_col, start_row, end_col, end_row) in moves {
+            assert!(play_move(&mut contract, start_col, start_row, end_col, end_row).is_moved());
+        }
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        let game_status: Game = contract.get_game_status(GAME);
+        assert!(!game_status.is_black_check());
+        assert!(game_status.get_result() == GameResult::Draw(DrawReason::Stalemate));
+
+        // No moves after the game is over.
+        assert!(play_move(&mut contract, 6, 2, 5, 3).get_error() == Some(ErrorResponse::GameOver));
+    }
+
+    #[test]
+    fn halfmove_clock() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        // Knights moving around don't reset the clock.
+        play_move(&mut contract, 6, 7, 5, 5);
+        play_move(&mut contract, 6, 0, 5, 2);
+        play_move(&mut contract, 5, 5, 6, 7);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_game_status(GAME).get_halfmove_clock(), 3);
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.claim_draw(GAME), "Draw can't be claimed yet.");
+
+        // Pawn moves reset the clock.
+        play_move(&mut contract, 4, 1, 4, 3);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_game_status(GAME).get_halfmove_clock(), 0);
+        assert!(!contract.get_game_status(GAME).is_game_over());
+    }
+
+    // A contract whose only game has kings and pawns locked on a4 and a5, so only the kings can move.
+    fn new_kings_contract() -> Chess {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = Chess::default();
+        assert_eq!(contract.create_game_from_fen(BLACK.to_string(), String::from("2k5/8/8/p7/P7/8/8/2K5 w - - 0 1")), GAME);
+
+        contract
+    }
+
+    // Turns of the kings walking around, with no capture or pawn move and never the same position 3 times.
+    // The white king goes around 18 squares on ranks 1 to 3, the black one around 17 on ranks 6 to 8.
+    fn walk_kings(contract: &mut Chess, plies: std::ops::Range<usize>) {
+        let white_path: [(u8, u8); 18] = [
+            (2, 7), (3, 7), (4, 7), (5, 7), (6, 7), (7, 7), (7, 6), (7, 5), (6, 5),
+            (6, 6), (5, 6), (5, 5), (4, 5), (4, 6), (3, 6), (3, 5), (2, 5), (2, 6),
+        ];
+        let black_path: Vec<(u8, u8)> = white_path.iter()
+            .map(|(col, row)| (*col, 7 - row))
+            .filter(|square| *square != (6, 1))
+            .collect();
+
+        for ply in plies {
+            let path: &[(u8, u8)] = match ply % 2 {
+                0 => &white_path,
+                _ => &black_path,
+            };
+            let (start_col, start_row) = path[(ply / 2) % path.len()];
+            let (end_col, end_row) = path[(ply / 2 + 1) % path.len()];
+            assert!(play_move(contract, start_col, start_row, end_col, end_row).is_moved(), "{}", ply);
+        }
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut contract = new_kings_contract();
+        walk_kings(&mut contract, 0..99);
+
+        let context = get_account_context(WHITE, vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.claim_draw(GAME), "Draw can't be claimed yet.");
+
+        walk_kings(&mut contract, 99..100);
+
+        let context = get_account_context(WHITE, vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.get_game_status(GAME).get_halfmove_clock(), 100);
+        assert_eq!(contract.claim_draw(GAME), "Draw claimed by the fifty-move rule.");
+        assert!(contract.get_game_status(GAME).get_result() == GameResult::Draw(DrawReason::FiftyMoveRule));
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        let mut contract = new_kings_contract();
+        walk_kings(&mut contract, 0..149);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(!contract.get_game_status(GAME).is_game_over());
+
+        // The 150th turn without progress ends the game without anyone claiming it.
+        walk_kings(&mut contract, 149..150);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        let game: Game = contract.get_game_status(GAME);
+        assert_eq!(game.get_halfmove_clock(), 150);
+        assert!(game.get_result() == GameResult::Draw(DrawReason::SeventyFiveMoveRule));
+    }
+
+    fn shuffle_knights(contract: &mut Chess) {
+        for (start_col, start_row, end_col, end_row) in [(6, 7, 5, 5), (6, 0, 5, 2), (5, 5, 6, 7), (5, 2, 6, 0)] {
+            assert!(play_move(contract, start_col, start_row, end_col, end_row).is_moved());
+        }
+    }
+
+    #[test]
+    fn repetition() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        // Starting position happens for the second time.
+        shuffle_knights(&mut contract);
+        assert_eq!(contract.claim_draw(GAME), "Draw can't be claimed yet.");
+
+        // Third time.
+        shuffle_knights(&mut contract);
+        assert_eq!(contract.claim_draw(GAME), "Draw claimed by threefold repetition.");
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(contract.get_game_status(GAME).get_result() == GameResult::Draw(DrawReason::ThreefoldRepetition));
+
+        // Without a claim, the game goes on until the fifth time.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+
+        for _ in 0..3 {
+            shuffle_knights(&mut contract);
+        }
+        play_move(&mut contract, 6, 7, 5, 5);
+        play_move(&mut contract, 6, 0, 5, 2);
+        play_move(&mut contract, 5, 5, 6, 7);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(!contract.get_game_status(GAME).is_game_over());
+
+        assert!(play_move(&mut contract, 5, 2, 6, 0).is_moved());
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(contract.get_game_status(GAME).get_result() == GameResult::Draw(DrawReason::FivefoldRepetition));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        // Black king on e8, black pawn on h6. White bishop on c1, white king on e1.
+        let mut squares: [u8; 64] = [0; 64];
+        squares[4] = 12;
+        squares[23] = 7;
+        squares[58] = 4;
+        squares[60] = 6;
+
+        let mut board: Board = Board::new(squares);
+        assert!(!board.is_insufficient_material());
+
+        // A rook, or two knights, are still enough to keep playing.
+        let mut rook_squares: [u8; 64] = [0; 64];
+        rook_squares[4] = 12;
+        rook_squares[56] = 2;
+        rook_squares[60] = 6;
+        assert!(!Board::new(rook_squares).is_insufficient_material());
+
+        let mut knights_squares: [u8; 64] = [0; 64];
+        knights_squares[4] = 12;
+        knights_squares[57] = 3;
+        knights_squares[60] = 6;
+        knights_squares[62] = 3;
+        assert!(!Board::new(knights_squares).is_insufficient_material());
+
+        let mut players: Players = Players::new(&board, false, 0, 0, WHITE.to_string(), BLACK.to_string());
+
+        // Bishop takes the last pawn, leaving K+B vs K.
+        let step: Step = Step::new_index(58, 23, &board);
+        assert!(players.move_to(&mut board, step, &WHITE.to_string()).is_ok());
+
+        assert!(board.is_insufficient_material());
+        assert!(players.get_turn().get_result() == GameResult::Draw(DrawReason::InsufficientMaterial));
+    }
+
+    #[test]
+    fn players() {
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        let mut contract = new_contract();
+        assert_eq!(contract.reset_game(GAME, WHITE.to_string()), "Can't play against yourself.");
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_game_status(GAME).get_white_player(), WHITE);
+        assert_eq!(contract.get_game_status(GAME).get_black_player(), BLACK);
+
+        // Black can't move for white, and other accounts can't move at all.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(GAME, 4, 6, 4, 4, None).get_error() == Some(ErrorResponse::NotYourTurn));
+
+        let context = get_account_context("mallory.testnet", vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(GAME, 4, 6, 4, 4, None).get_error() == Some(ErrorResponse::NotYourTurn));
+        assert_eq!(contract.claim_draw(GAME), "Only the players can claim a draw.");
+        assert_eq!(contract.reset_game(GAME, "mallory.testnet".to_string()), "Only the players can reset a game.");
+
+        assert!(play_move(&mut contract, 4, 6, 4, 4).is_moved());
+
+        // White already moved, now it's black's turn.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(GAME, 3, 6, 3, 4, None).get_error() == Some(ErrorResponse::NotYourTurn));
+
+        assert!(play_move(&mut contract, 4, 1, 4, 3).is_moved());
+
+        // Not even once the game is over.
+        assert_eq!(contract.resign(GAME), "White wins by resignation.");
+        let context = get_account_context("mallory.testnet", vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.reset_game(GAME, "mallory.testnet".to_string()), "Only the players can reset a game.");
+        assert_eq!(contract.get_history_length(GAME), 2);
+    }
+
+    #[test]
+    fn multiple_games() {
+        let mut contract = new_contract();
+
+        // Same players, with colors swapped.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        let other_game: GameId = contract.create_game(WHITE.to_string());
+        assert_eq!(other_game, U64(GAME.0 + 1));
+
+        assert!(contract.move_to(other_game, 3, 6, 3, 4, None).is_moved());
+        assert!(play_move(&mut contract, 4, 6, 4, 4).is_moved());
+
+        // Each game keeps its own board and turn.
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        let game: Game = contract.get_game_status(GAME);
+        let other: Game = contract.get_game_status(other_game);
+
+        assert_eq!(game.get_squares()[36], 1);
+        assert_eq!(game.get_squares()[35], 0);
+        assert_eq!(other.get_squares()[35], 1);
+        assert_eq!(other.get_squares()[36], 0);
+        assert_eq!(other.get_white_player(), BLACK);
+        assert!(game.get_player_turn() && other.get_player_turn());
+    }
+
+    #[test]
+    #[should_panic]
+    fn game_not_found() {
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        let contract = Chess::default();
+        contract.get_board(GAME);
+    }
+
+    #[test]
+    fn history() {
+        let mut contract = new_contract();
+
+        let moves: [(u8, u8, u8, u8); 7] = [
+            (4, 6, 4, 4), (5, 1, 5, 2),
+            (3, 6, 3, 4), (6, 1, 6, 3),
+            (2, 7, 6, 3), (7, 1, 7, 2),
+            (3, 7, 7, 3),
+        ];
+
+        for (start_col, start_row, end_col, end_row) in moves {
+            assert!(play_move(&mut contract, start_col, start_row, end_col, end_row).is_moved());
+        }
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        assert_eq!(contract.get_history_length(GAME), 7);
+        assert_eq!(contract.get_history(GAME, 0, 100).len(), 7);
+
+        let first: MoveRecord = contract.get_history(GAME, 0, 1)[0].clone();
+        assert_eq!((first.get_from(), first.get_to(), first.get_piece(), first.get_captured()), (52, 36, 1, 0));
+        assert!(!first.is_check());
+
+        // Bishop takes the pawn on g5.
+        let page: Vec<MoveRecord> = contract.get_history(GAME, 4, 2);
+        assert_eq!(page.len(), 2);
+        assert_eq!((page[0].get_piece(), page[0].get_captured()), (4, 7));
+
+        // Queen to h5 is checkmate.
+        let last: Vec<MoveRecord> = contract.get_history(GAME, 6, 10);
+        assert_eq!(last.len(), 1);
+        assert_eq!((last[0].get_from(), last[0].get_to(), last[0].get_piece()), (59, 31, 5));
+        assert!(last[0].is_check() && last[0].is_checkmate());
+
+        assert!(contract.get_history(GAME, 7, 10).is_empty());
+    }
+
+    #[test]
+    fn fen() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(GAME), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        play_move(&mut contract, 4, 6, 4, 4);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(GAME), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        // One move away from the fifty-move rule.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/8/8/8/8/R3K2R w KQ - 99 40"));
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(fen_game), "4k3/8/8/8/8/8/8/R3K2R w KQ - 99 40");
+
+        // Only the turns played here count for the limit of 255.
+        let game: Game = contract.get_game_status(fen_game);
+        assert_eq!((game.get_turn(), game.get_start_turn()), (0, 78));
+        assert_eq!(game.get_halfmove_clock(), 99);
+        assert!(game.get_castling_rights().can_castle(&pieces::piece::Color::WHITE, false));
+        assert!(!game.get_castling_rights().can_castle(&pieces::piece::Color::BLACK, false));
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(fen_game, 0, 7, 0, 6, None).is_moved());
+        assert_eq!(contract.claim_draw(fen_game), "Draw claimed by the fifty-move rule.");
+
+        // A position that is already checkmate ends the game right away.
+        let mate_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"));
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        let game: Game = contract.get_game_status(mate_game);
+        assert!(game.is_black_check());
+        assert!(game.get_result() == GameResult::WhiteWins(WinReason::Checkmate));
+
+        // A game late in the move count plays on like any other.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let late_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/8/8/8/8/R3K3 b - - 0 40000"));
+        testing_env!(get_account_context(BLACK, vec![], false));
+        assert!(contract.move_san(late_game, String::from("Kd7")).is_moved());
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(late_game), "8/3k4/8/8/8/8/8/R3K3 w - - 1 40001");
+        assert!(!contract.get_game_status(late_game).is_game_over());
+    }
+
+    #[test]
+    fn fen_errors() {
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        let errors: [(&str, FenError); 11] = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -", FenError::FieldCount),
+            ("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", FenError::InvalidBoard),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKXNR w KQkq - 0 1", FenError::InvalidBoard),
+            ("4k3/8/8/8/8/8/8/4KK2 w - - 0 1", FenError::InvalidKings),
+            ("P3k3/8/8/8/8/8/8/4K3 w - - 0 1", FenError::InvalidPawns),
+            ("4k3/8/8/8/8/8/8/4K3 x - - 0 1", FenError::InvalidPlayer),
+            ("4k3/8/8/8/8/8/8/4K3 w K - 0 1", FenError::InvalidCastling),
+            ("4k3/8/8/8/4P3/8/8/4K3 b - e4 0 1", FenError::InvalidEnPassant),
+            ("4k3/8/8/8/8/8/8/4RK2 w - - 0 1", FenError::RivalInCheck),
+            ("4k3/8/8/8/8/8/8/4K3 b - - 0 0", FenError::InvalidClock),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 70000", FenError::InvalidClock),
+        ];
+
+        for (fen_string, expected) in errors {
+            match fen::parse_fen(fen_string) {
+                Result::Ok(_) => panic!("FEN should be invalid: {}", fen_string),
+                Result::Err(err) => assert_eq!(err.get_message(), expected.get_message(), "{}", fen_string),
+            }
+        }
+
+        assert!(fen::parse_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").is_ok());
+        assert!(fen::parse_fen("4k3/8/8/8/8/8/8/4K3 w - - 150 1").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid FEN")]
+    fn fen_invalid_game() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        contract.create_game_from_fen(BLACK.to_string(), String::from("8/8/8/8/8/8/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn pgn() {
+        let mut contract = new_contract();
+
+        // Two knights can reach d2, so the one from b1 is written as Nbd2.
+        let moves: [(u8, u8, u8, u8); 5] = [
+            (3, 6, 3, 4), (6, 0, 5, 2),
+            (6, 7, 5, 5), (1, 0, 2, 2),
+            (1, 7, 3, 6),
+        ];
+
+        for (start_col, start_row, end_col, end_row) in moves {
+            assert!(play_move(&mut contract, start_col, start_row, end_col, end_row).is_moved());
+        }
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_pgn(GAME), [
+            "[Event \"Chess game 0\"]",
+            "[Site \"alice.testnet\"]",
+            "[Date \"1970.01.01\"]",
+            "[Round \"-\"]",
+            "[White \"jane.testnet\"]",
+            "[Black \"bob.testnet\"]",
+            "[Result \"*\"]",
+            "",
+            "1. d4 Nf6 2. Nf3 Nc6 3. Nbd2 *",
+            "",
+        ].join("\n"));
+
+        // Captures, checkmate and result.
+        let mut contract = new_contract();
+
+        let moves: [(u8, u8, u8, u8); 7] = [
+            (4, 6, 4, 4), (5, 1, 5, 2),
+            (3, 6, 3, 4), (6, 1, 6, 3),
+            (2, 7, 6, 3), (7, 1, 7, 2),
+            (3, 7, 7, 3),
+        ];
+
+        for (start_col, start_row, end_col, end_row) in moves {
+            play_move(&mut contract, start_col, start_row, end_col, end_row);
+        }
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        let pgn: String = contract.get_pgn(GAME);
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(pgn.ends_with("\n1. e4 f6 2. d4 g5 3. Bxg5 h6 4. Qh5# 1-0\n"));
+
+        // Games from FEN say where they started, and may start with black.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/8/8/8/8/R3K2R b KQ - 0 10"));
+
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(fen_game, 4, 0, 3, 0, None).is_moved());
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert!(contract.move_to(fen_game, 4, 7, 2, 7, None).is_moved());
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        let pgn: String = contract.get_pgn(fen_game);
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K2R b KQ - 0 10\"]\n"));
+        assert!(pgn.ends_with("\n10... Kd8 11. O-O-O+ *\n"));
+
+        assert_eq!(pgn::get_date(1_700_000_000_000_000_000), "2023.11.14");
+    }
+
+    // Morphy's Opera Game, Paris 1858.
+    const OPERA_GAME: &str = "1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6\n\
+        9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6\n\
+        15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0\n";
+
+    #[test]
+    fn pgn_import() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        // Tags, comments, variations and annotations are ignored.
+        let pgn_text: String = format!(
+            "[Event \"Paris\"]\n[White \"Morphy\"]\n[Black \"Duke Karl / Count Isouard\"]\n\n{{A famous game}} {} ",
+            OPERA_GAME.replace("3. d4", "3. d4 $1 (3. Bc4 Be7) ; the best move\n"),
+        );
+        let game_id: GameId = contract.create_game_from_pgn(BLACK.to_string(), pgn_text);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(contract.get_game_status(game_id).get_result() == GameResult::WhiteWins(WinReason::Checkmate));
+        assert_eq!(contract.get_history_length(game_id), 33);
+
+        // Exporting gives back the same moves.
+        let pgn_text: String = contract.get_pgn(game_id);
+        let move_text: &str = pgn_text.split("\n\n").nth(1).unwrap();
+        assert_eq!(move_text.split_whitespace().collect::<Vec<&str>>(), OPERA_GAME.split_whitespace().collect::<Vec<&str>>());
+
+        // Games from FEN come back the same way.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let game_id: GameId = contract.create_game_from_pgn(
+            BLACK.to_string(),
+            String::from("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K2R b KQ - 0 10\"]\n\n10... Kd8 11. O-O-O+ *"),
+        );
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(game_id), "3k4/8/8/8/8/8/8/2KR3R b - - 2 11");
+
+        // "e.p." can be apart from the move.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let game_id: GameId = contract.create_game_from_pgn(
+            BLACK.to_string(),
+            String::from("[SetUp \"1\"]\n[FEN \"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2\"]\n\n2. exd6 e.p. Kd7 *"),
+        );
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(game_id), "8/3k4/3P4/8/8/8/8/4K3 w - - 1 3");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid move at ply 3 (Ke3). No valid move matches it.")]
+    fn pgn_import_invalid_move() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        contract.create_game_from_pgn(BLACK.to_string(), String::from("1. e4 e5 2. Ke3 Nc6 *"));
+    }
+
+    #[test]
+    fn move_san() {
+        let mut contract = new_contract();
+
+        // Naming the pawn is fine too.
+        let moves: [(&str, &str); 6] = [
+            ("Pe4", WHITE), ("d5", BLACK),
+            ("exd5", WHITE), ("Nf6", BLACK),
+            ("Bb5+", WHITE), ("c6", BLACK),
+        ];
+
+        for (san, account_id) in moves {
+            let context = get_account_context(account_id, vec![], false);
+            testing_env!(context);
+            assert!(contract.move_san(GAME, String::from(san)).is_moved(), "{}", san);
+        }
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        // Not a valid move, or written wrong.
+        assert!(contract.move_san(GAME, String::from("Ke3")).get_error() == Some(ErrorResponse::NoMatchingMove));
+        assert!(contract.move_san(GAME, String::from("nf3")).get_error() == Some(ErrorResponse::NoMatchingMove));
+
+        // Only the current player can move.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert!(contract.move_san(GAME, String::from("Nf3")).get_error() == Some(ErrorResponse::NotYourTurn));
+
+        // Castling, with "0-0" written with zeros. The knight's file isn't needed, but it's right.
+        for (san, account_id) in [("Ngf3", WHITE), ("cxb5", BLACK), ("0-0", WHITE)] {
+            let context = get_account_context(account_id, vec![], false);
+            testing_env!(context);
+            assert!(contract.move_san(GAME, String::from(san)).is_moved(), "{}", san);
+        }
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(GAME), "rnbqkb1r/pp2pppp/5n2/1p1P4/8/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 5");
+
+        // Promotion, from a position one move away from it.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(contract.move_san(fen_game, String::from("b8")).get_error() == Some(ErrorResponse::NoMatchingMove));
+        assert!(contract.move_san(fen_game, String::from("b8=R+")).is_moved());
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(fen_game), "1R2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        // Promotion without "=".
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(contract.move_san(fen_game, String::from("b8Q+")).is_moved());
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert_eq!(contract.get_fen(fen_game), "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        // "x" must agree with the move: no capture written for one, and none for a quiet move.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/3p4/4P3/8/8/4K1N1 w - - 0 1"));
+        assert!(contract.move_san(fen_game, String::from("d5")).get_error() == Some(ErrorResponse::NoMatchingMove));
+        assert!(contract.move_san(fen_game, String::from("Nxf3")).get_error() == Some(ErrorResponse::NoMatchingMove));
+        assert!(contract.move_san(fen_game, String::from("exd5")).is_moved());
+    }
+
+    #[test]
+    fn move_uci() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        match contract.move_uci(GAME, String::from("e2e4")) {
+            MoveOutcome::Rejected{ reason, .. } => panic!("{}", reason),
+            MoveOutcome::Moved{ record, result } => {
+                // Same indexes used by move_to_index.
+                assert_eq!((record.get_from(), record.get_to(), record.get_san()), (52, 36, String::from("e4")));
+                assert!(result == GameResult::Running);
+            },
+        }
+
+        // Not UCI notation, or not a valid move.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        for uci in ["e7", "e7e5x", "i7i5", "e7e8k"] {
+            match contract.move_uci(GAME, String::from(uci)) {
+                MoveOutcome::Moved{ .. } => panic!("{} should be rejected", uci),
+                MoveOutcome::Rejected{ error, .. } => assert!(error == ErrorResponse::InvalidNotation, "{}", uci),
+            }
+        }
+        assert!(contract.move_uci(GAME, String::from("e7e4")).get_error() == Some(ErrorResponse::InvalidMove));
+
+        // Promotion letter at the end, checkmate in the result.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("k7/2P5/1K6/8/8/8/8/8 w - - 0 1"));
+
+        match contract.move_uci(fen_game, String::from("c7c8q")) {
+            MoveOutcome::Rejected{ reason, .. } => panic!("{}", reason),
+            MoveOutcome::Moved{ record, result } => {
+                assert_eq!(record.get_san(), "c8=Q#");
+                assert!(record.get_promotion() == Some(Promotion::QUEEN));
+                assert!(result == GameResult::WhiteWins(WinReason::Checkmate));
+            },
+        }
+    }
+
+    #[test]
+    fn move_errors() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+
+        // Outside the board, by column and row or by index.
+        match contract.move_to(GAME, 8, 6, 4, 4, None) {
+            MoveOutcome::Moved{ .. } => panic!("Column 8 should be rejected"),
+            MoveOutcome::Rejected{ error, reason } => {
+                assert!(error == ErrorResponse::OutOfRange);
+                assert_eq!(reason, "Invalid position. Column and row must be from 0 to 7.");
+            },
+        }
+        assert!(contract.move_to_index(GAME, 52, 64, None).get_error() == Some(ErrorResponse::OutOfRange));
+
+        // Empty position, and a piece of the other color.
+        assert!(contract.move_to(GAME, 4, 4, 4, 3, None).get_error() == Some(ErrorResponse::NoPiece));
+        assert!(contract.move_to(GAME, 4, 1, 4, 3, None).get_error() == Some(ErrorResponse::RivalPiece));
+
+        // The bishop can't leave the king open to the rook.
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1"));
+        match contract.move_uci(fen_game, String::from("e2d3")) {
+            MoveOutcome::Moved{ .. } => panic!("e2d3 should be rejected"),
+            MoveOutcome::Rejected{ error, reason } => {
+                assert!(error == ErrorResponse::SelfCheck);
+                assert_eq!(reason, ErrorResponse::SelfCheck.get_message());
+            },
+        }
+        assert!(contract.move_uci(fen_game, String::from("e1d1")).is_moved());
+    }
+
+    #[test]
+    fn legal_moves() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+
+        // 16 pawn moves and 4 knight moves.
+        assert_eq!(contract.get_legal_moves(GAME).len(), 20);
+
+        let knight_moves: Vec<(String, String)> = contract.get_legal_moves_from(GAME, String::from("g1"))
+            .iter()
+            .map(|legal_move| (legal_move.get_san(), legal_move.get_uci()))
+            .collect();
+        assert_eq!(knight_moves.len(), 2);
+        assert!(knight_moves.contains(&(String::from("Nf3"), String::from("g1f3"))));
+        assert!(knight_moves.contains(&(String::from("Nh3"), String::from("g1h3"))));
+
+        // Black pieces can't move in white's turn.
+        assert!(contract.get_legal_moves_from(GAME, String::from("g8")).is_empty());
+        assert!(contract.get_legal_moves_from(GAME, String::from("z9")).is_empty());
+
+        // Check, castling and every promotion.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"));
+
+        let rook_check = contract.get_legal_moves_from(fen_game, String::from("a1"))
+            .into_iter()
+            .find(|legal_move| legal_move.get_uci() == "a1a8")
+            .unwrap();
+        assert_eq!(rook_check.get_san(), "Ra8+");
+        assert!(rook_check.is_check() && !rook_check.is_checkmate());
+
+        let castling = contract.get_legal_moves_from(fen_game, String::from("e1"))
+            .into_iter()
+            .find(|legal_move| legal_move.is_castling())
+            .unwrap();
+        assert_eq!((castling.get_san(), castling.get_uci()), (String::from("O-O-O"), String::from("e1c1")));
+
+        let promotion_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("k7/2P5/1K6/8/8/8/8/8 w - - 0 1"));
+        let promotions: Vec<String> = contract.get_legal_moves_from(promotion_game, String::from("c7"))
+            .iter()
+            .map(|legal_move| legal_move.get_san())
+            .collect();
+        assert_eq!(promotions, vec!["c8=Q#", "c8=R#", "c8=B", "c8=N"]);
+
+        // Nothing to play once the game is over.
+        contract.move_uci(promotion_game, String::from("c7c8q"));
+        assert!(contract.get_legal_moves(promotion_game).is_empty());
+    }
+
+    #[test]
+    fn events() {
+        let mut contract = new_contract();
+
+        // Fool's mate.
+        play_move(&mut contract, 5, 6, 5, 5);
+        play_move(&mut contract, 4, 1, 4, 3);
+        play_move(&mut contract, 6, 6, 6, 4);
+
+        let context = get_turn_context(&contract);
+        testing_env!(context);
+        assert!(contract.move_san(GAME, String::from("Qh4")).is_moved());
+
+        let events: Vec<String> = test_utils::get_logs()
+            .into_iter()
+            .filter(|line| line.starts_with("EVENT_JSON:"))
+            .collect();
+        let expected: Vec<String> = vec![
+            format!(
+                "EVENT_JSON:{{\"standard\":\"chess\",\"version\":\"1.0.0\",\"event\":\"move_made\",\"data\":[{{\"game_id\":\"0\",\"player\":\"{}\",\"san\":\"Qh4#\",\"uci\":\"d8h4\",\"fen\":\"rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3\"}}]}}",
+                BLACK,
+            ),
+            format!(
+                "EVENT_JSON:{{\"standard\":\"chess\",\"version\":\"1.0.0\",\"event\":\"check\",\"data\":[{{\"game_id\":\"0\",\"player\":\"{}\",\"checkmate\":true}}]}}",
+                WHITE,
+            ),
+            format!(
+                "EVENT_JSON:{{\"standard\":\"chess\",\"version\":\"1.0.0\",\"event\":\"game_ended\",\"data\":[{{\"game_id\":\"0\",\"result\":{{\"BlackWins\":\"Checkmate\"}},\"description\":\"{}\",\"winner\":\"{}\"}}]}}",
+                GameResult::BlackWins(WinReason::Checkmate).get_description(),
+                BLACK,
+            ),
+        ];
+        assert_eq!(events, expected);
+
+        // A rejected move has no events.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        contract.move_san(GAME, String::from("e4"));
+        assert!(test_utils::get_logs().iter().all(|line| !line.starts_with("EVENT_JSON:")));
+
+        // New games say who plays them.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let game_id: GameId = contract.create_game(BLACK.to_string());
+        assert_eq!(
+            test_utils::get_logs(),
+            vec![format!(
+                "EVENT_JSON:{{\"standard\":\"chess\",\"version\":\"1.0.0\",\"event\":\"game_created\",\"data\":[{{\"game_id\":\"{}\",\"white\":\"{}\",\"black\":\"{}\",\"start_fen\":null,\"time_control\":null,\"days_per_move\":null}}]}}",
+                game_id.0,
+                WHITE,
+                BLACK,
+            )],
+        );
+    }
+
+    #[test]
+    fn resign() {
+        let mut contract = new_contract();
+
+        // Only the players can resign.
+        let context = get_account_context("someone.testnet", vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.resign(GAME), "Only the players can resign.");
+
+        // Either player can resign, even in the rival's turn.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.resign(GAME), "White wins by resignation.");
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        assert!(contract.get_game_status(GAME).get_result() == GameResult::WhiteWins(WinReason::Resignation));
+        assert!(contract.get_pgn(GAME).ends_with("1-0\n"));
+
+        // Nothing else can happen after it.
+        assert!(play_move(&mut contract, 4, 6, 4, 4).get_error() == Some(ErrorResponse::GameOver));
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.resign(GAME), "Game already over, check status or call reset.");
+    }
+
+    #[test]
+    fn draw_offers() {
+        let mut contract = new_contract();
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.accept_draw(GAME), "There's no draw offer from your rival.");
+        assert_eq!(contract.offer_draw(GAME), "Draw offered.");
+        assert_eq!(contract.offer_draw(GAME), "You already offered a draw.");
+        // Can't accept their own offer.
+        assert_eq!(contract.accept_draw(GAME), "There's no draw offer from your rival.");
+
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.decline_draw(GAME), "Draw declined.");
+        assert_eq!(contract.decline_draw(GAME), "There's no draw offer from your rival.");
+
+        // The offer is taken back when the offering player moves.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        contract.offer_draw(GAME);
+        play_move(&mut contract, 4, 6, 4, 4);
+        assert!(contract.get_game_status(GAME).get_draw_offer().is_none());
+
+        // But not when the rival moves.
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        contract.offer_draw(GAME);
+        play_move(&mut contract, 4, 1, 4, 3);
+        assert_eq!(contract.get_game_status(GAME).get_draw_offer(), Some(WHITE.to_string()));
+
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        assert_eq!(contract.offer_draw(GAME), "Your rival already offered a draw, call accept_draw to take it.");
+        assert_eq!(contract.accept_draw(GAME), "Draw by agreement.");
+        assert!(test_utils::get_logs().iter().any(|line| line.contains("\"event\":\"game_ended\"")));
+
+        assert!(contract.get_game_status(GAME).get_result() == GameResult::Draw(DrawReason::Agreement));
+        assert!(contract.get_game_status(GAME).get_draw_offer().is_none());
+    }
+
+    #[test]
+    fn clocks() {
+        let mut contract = new_contract();
+        let seconds = |remaining: near_sdk::json_types::U64| remaining.0 / 1_000_000_000;
+
+        // Fischer: the whole increment comes back after each move.
+        testing_env!(get_time_context(WHITE, 0));
+        let fischer: GameId = contract.create_timed_game(BLACK.to_string(), TimeControl::new(60, 2, ClockMode::Fischer));
+
+        testing_env!(get_time_context(WHITE, 10));
+        assert!(contract.move_uci(fischer, String::from("e2e4")).is_moved());
+        testing_env!(get_time_context(BLACK, 15));
+        assert!(contract.move_uci(fischer, String::from("e7e5")).is_moved());
+
+        // White's time is running again.
+        testing_env!(get_time_context(WHITE, 20));
+        let clock: Clock = contract.get_clock(fischer).unwrap();
+        assert_eq!((seconds(clock.get_white_remaining()), seconds(clock.get_black_remaining())), (47, 57));
+
+        // Bronstein: only the time spent comes back, up to the increment.
+        testing_env!(get_time_context(WHITE, 0));
+        let bronstein: GameId = contract.create_timed_game(BLACK.to_string(), TimeControl::new(60, 3, ClockMode::Bronstein));
+
+        testing_env!(get_time_context(WHITE, 10));
+        assert!(contract.move_uci(bronstein, String::from("e2e4")).is_moved());
+        testing_env!(get_time_context(BLACK, 11));
+        assert!(contract.move_uci(bronstein, String::from("e7e5")).is_moved());
+
+        let clock: Clock = contract.get_clock(bronstein).unwrap();
+        assert_eq!((seconds(clock.get_white_remaining()), seconds(clock.get_black_remaining())), (53, 60));
+        assert!(contract.get_clock(GAME).is_none());
+
+        // In the Fischer game, white has 52 seconds from 15, so until 67.
+        testing_env!(get_time_context(BLACK, 66));
+        assert_eq!(contract.claim_timeout(fischer), "Your rival still has time left.");
+        // Only the rival can claim it.
+        testing_env!(get_time_context(WHITE, 67));
+        assert_eq!(contract.claim_timeout(fischer), "Only the rival of the player to move can claim a timeout.");
+
+        testing_env!(get_time_context(BLACK, 67));
+        assert_eq!(contract.claim_timeout(fischer), "Black wins by timeout.");
+
+        // Moving too late ends the game too.
+        testing_env!(get_time_context(WHITE, 200));
+        assert!(contract.move_uci(bronstein, String::from("g1f3")).get_error() == Some(ErrorResponse::OutOfTime));
+        assert!(contract.get_game_status(bronstein).get_result() == GameResult::BlackWins(WinReason::Timeout));
+
+        // A lone king can't win on time.
+        testing_env!(get_time_context(WHITE, 0));
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k3/8/8/8/8/8/8/4K2R w - - 0 1"));
+        let mut game: Game = contract.load_game(fen_game);
+        game.set_time_control(TimeControl::new(60, 0, ClockMode::Fischer));
+        contract.save_game(fen_game, &game);
+
+        testing_env!(get_time_context(BLACK, 61));
+        assert_eq!(contract.claim_timeout(fen_game), "Draw by timeout vs insufficient material.");
+
+        // A single knight can't mate a lone king, but it can mate one blocked by its own pawn.
+        testing_env!(get_time_context(WHITE, 0));
+        let fen_game: GameId = contract.create_game_from_fen(BLACK.to_string(), String::from("4k1n1/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        let mut game: Game = contract.load_game(fen_game);
+        game.set_time_control(TimeControl::new(60, 0, ClockMode::Fischer));
+        contract.save_game(fen_game, &game);
+
+        testing_env!(get_time_context(BLACK, 61));
+        assert_eq!(contract.claim_timeout(fen_game), "Black wins by timeout.");
+    }
+
+    #[test]
+    #[should_panic(expected = "Base time and increment can't be over 604800 seconds.")]
+    fn time_control_too_long() {
+        let mut contract = new_contract();
+
+        contract.create_timed_game(BLACK.to_string(), TimeControl::new(60, u32::MAX, ClockMode::Fischer));
+    }
+
+    #[test]
+    fn correspondence() {
+        let mut contract = new_contract();
+        const DAY: u64 = 86_400;
+
+        testing_env!(get_time_context(WHITE, 0));
+        let game_id: GameId = contract.create_correspondence_game(BLACK.to_string(), 1, 2);
+        assert_eq!(contract.get_games_to_move(WHITE.to_string(), 0, 10), vec![GAME, game_id]);
+        assert!(contract.get_games_to_move(BLACK.to_string(), 0, 10).is_empty());
+
+        // Black has one day from the white move.
+        testing_env!(get_time_context(WHITE, DAY / 2));
+        assert!(contract.move_uci(game_id, String::from("e2e4")).is_moved());
+        assert_eq!(contract.get_games_to_move(WHITE.to_string(), 0, 10), vec![GAME]);
+        assert_eq!(contract.get_games_to_move(BLACK.to_string(), 0, 10), vec![game_id]);
+
+        // Pages go over the games of the account, so one can be empty before the next has some.
+        assert_eq!(contract.get_account_games_length(BLACK.to_string()), 2);
+        assert!(contract.get_games_to_move(BLACK.to_string(), 0, 1).is_empty());
+        assert_eq!(contract.get_games_to_move(BLACK.to_string(), 1, 1), vec![game_id]);
+
+        // Only the player to move can take vacation days, as many as they have left.
+        testing_env!(get_time_context(WHITE, DAY));
+        assert_eq!(contract.take_vacation(game_id, 1), "Only the player to move can take vacation days.");
+        testing_env!(get_time_context(BLACK, DAY));
+        assert_eq!(contract.take_vacation(game_id, 3), "Only 2 vacation days left.");
+        assert_eq!(contract.take_vacation(game_id, 0), "Must take at least 1 vacation day.");
+        assert_eq!(contract.take_vacation(game_id, 2), "Vacation taken, 0 days left.");
+        assert_eq!(contract.take_vacation(GAME, 1), "Only the player to move can take vacation days.");
+
+        let correspondence: Correspondence = contract.get_game_status(game_id).get_correspondence_game().unwrap();
+        assert_eq!(correspondence.get_deadline().0, (DAY / 2 + 3 * DAY) * 1_000_000_000);
+
+        testing_env!(get_time_context(WHITE, 3 * DAY));
+        assert_eq!(contract.claim_timeout(game_id), "Your rival still has time left.");
+        testing_env!(get_time_context(WHITE, DAY / 2 + 3 * DAY));
+        assert_eq!(contract.claim_timeout(game_id), "White wins by timeout.");
+        assert!(contract.get_games_to_move(BLACK.to_string(), 0, 10).is_empty());
+
+        // A game without any time control can't time out.
+        testing_env!(get_time_context(BLACK, 365 * DAY));
+        assert_eq!(contract.claim_timeout(GAME), "This game has no clock.");
+    }
+
+    #[test]
+    #[should_panic(expected = "Days per move and vacation days can't be over 365.")]
+    fn correspondence_too_many_days() {
+        let mut contract = new_contract();
+
+        contract.create_correspondence_game(BLACK.to_string(), 1, 300_000);
+    }
+
+    #[test]
+    fn challenges() {
+        let mut contract = new_contract();
+        const CAROL: &str = "carol.testnet";
+
+        let context = get_context(vec![], false);
+        testing_env!(context);
+        let open: ChallengeId = contract.create_challenge(None, ColorPreference::Black, Some(TimeControl::new(300, 2, ClockMode::Fischer)), false);
+
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        let direct: ChallengeId = contract.create_challenge(Some(WHITE.to_string()), ColorPreference::Random, None, false);
+
+        let context = get_context(vec![], true);
+        testing_env!(context);
+        let listed: Vec<ChallengeId> = contract.list_open_challenges(0, 10).iter().map(|challenge| challenge.get_id()).collect();
+        assert_eq!(listed, vec![open, direct]);
+        assert_eq!(contract.list_open_challenges(1, 10).len(), 1);
+
+        // Anyone can accept an open challenge, and gets the color the challenger didn't want.
+        let context = get_account_context(CAROL, vec![], false);
+        testing_env!(context);
+        let game_id: GameId = contract.accept_challenge(open);
+
+        let game: Game = contract.get_game_status(game_id);
+        assert_eq!((game.get_white_player(), game.get_black_player()), (CAROL.to_string(), WHITE.to_string()));
+        assert!(game.get_time_control() == Some(TimeControl::new(300, 2, ClockMode::Fischer)));
+        assert_eq!(contract.get_games_to_move(CAROL.to_string(), 0, 10), vec![game_id]);
+
+        // Only the challenger can take it back.
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        contract.cancel_challenge(direct);
+        assert!(contract.list_open_challenges(0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "This challenge can't be accepted by carol.testnet.")]
+    fn challenge_for_someone_else() {
+        let mut contract = new_contract();
+
+        let context = get_account_context(BLACK, vec![], false);
+        testing_env!(context);
+        let challenge_id: ChallengeId = contract.create_challenge(Some(WHITE.to_string()), ColorPreference::Random, None, false);
+
+        let context = get_account_context("carol.testnet", vec![], false);
+        testing_env!(context);
+        contract.accept_challenge(challenge_id);
+    }
+
+    #[test]
+    fn wagers() {
+        let mut contract = new_contract();
+
+        testing_env!(get_deposit_context(WHITE, 10));
+        let challenge_id: ChallengeId = contract.create_challenge(None, ColorPreference::White, Some(TimeControl::new(300, 0, ClockMode::Fischer)), false);
+        assert_eq!(contract.list_open_challenges(0, 1)[0].get_wager().0, 10);
+
+        // Both players put the wager in the pot.
+        testing_env!(get_deposit_context(BLACK, 10));
+        let game_id: GameId = contract.accept_challenge(challenge_id);
+        let game: Game = contract.get_game_status(game_id);
+        assert_eq!((game.get_wager().0, game.get_pot().0), (10, 20));
+        assert_eq!(contract.reset_game(game_id, BLACK.to_string()), "Games with a wager can't be reset.");
+
+        // The winner takes the pot.
+        assert_eq!(contract.resign(game_id), "White wins by resignation.");
+        assert_eq!(contract.get_game_status(game_id).get_pot().0, 0);
+
+        // A game can be aborted until both players moved, and the wagers are given back.
+        testing_env!(get_deposit_context(WHITE, 10));
+        let challenge_id: ChallengeId = contract.create_challenge(Some(BLACK.to_string()), ColorPreference::White, Some(TimeControl::new(300, 0, ClockMode::Fischer)), false);
+        testing_env!(get_deposit_context(BLACK, 10));
+        let game_id: GameId = contract.accept_challenge(challenge_id);
+
+        testing_env!(get_account_context(WHITE, vec![], false));
+        assert!(contract.move_san(game_id, "e4".to_string()).is_moved());
+        assert_eq!(contract.abort_game(game_id), "Game aborted.");
+        let game: Game = contract.get_game_status(game_id);
+        assert!(game.get_result() == GameResult::Aborted);
+        assert_eq!(game.get_pot().0, 0);
+
+        // Not after both players moved.
+        assert!(contract.move_san(GAME, "e4".to_string()).is_moved());
+        testing_env!(get_account_context(BLACK, vec![], false));
+        assert!(contract.move_san(GAME, "e5".to_string()).is_moved());
+        assert_eq!(contract.abort_game(GAME), "Both players already moved, the game can't be aborted.");
+    }
+
+    #[test]
+    #[should_panic(expected = "Must attach the wager of the challenge (10 yoctoNEAR).")]
+    fn wager_not_matched() {
+        let mut contract = new_contract();
+
+        testing_env!(get_deposit_context(WHITE, 10));
+        let challenge_id: ChallengeId = contract.create_challenge(None, ColorPreference::Random, Some(TimeControl::new(300, 0, ClockMode::Fischer)), false);
+
+        testing_env!(get_deposit_context(BLACK, 5));
+        contract.accept_challenge(challenge_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The wager can't be over")]
+    fn wager_too_big() {
+        let mut contract = new_contract();
+
+        testing_env!(get_context(vec![], false));
+        contract.create_token_challenge(None, ColorPreference::Random, Some(TimeControl::new(300, 0, ClockMode::Fischer)), false, "token.testnet".to_string(), U128(u128::MAX / 2 + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "A challenge with a wager must have a time control.")]
+    fn wager_without_clock() {
+        let mut contract = new_contract();
+
+        testing_env!(get_deposit_context(WHITE, 10));
+        contract.create_challenge(None, ColorPreference::Random, None, false);
+    }
+
+    #[test]
+    fn token_wagers() {
+        let mut contract = new_contract();
+        const TOKEN: &str = "token.testnet";
+        let msg = |challenge_id: ChallengeId| format!("{{\"challenge_id\": \"{}\"}}", challenge_id.0);
+
+        testing_env!(get_context(vec![], false));
+        let challenge_id: ChallengeId = contract.create_token_challenge(None, ColorPreference::White, Some(TimeControl::new(300, 0, ClockMode::Fischer)), false, TOKEN.to_string(), U128(100));
+        assert!(!contract.list_open_challenges(0, 1)[0].is_funded());
+
+        // The token contract calls ft_on_transfer, first with the wager of the challenger, then with the one accepting.
+        testing_env!(get_account_context(TOKEN, vec![], false));
+        contract.ft_on_transfer(WHITE.to_string(), U128(100), msg(challenge_id));
+        assert!(contract.list_open_challenges(0, 1)[0].is_funded());
+
+        match contract.ft_on_transfer(BLACK.to_string(), U128(100), msg(challenge_id)) {
+            PromiseOrValue::Value(unused) => assert_eq!(unused.0, 0),
+            PromiseOrValue::Promise(_) => panic!("Expected every token to be used."),
+        }
+        let game_id: GameId = *contract.get_account_games(&BLACK.to_string()).last().unwrap();
+        let game: Game = contract.get_game_status(game_id);
+        assert_eq!((game.get_white_player(), game.get_black_player()), (WHITE.to_string(), BLACK.to_string()));
+        assert_eq!((game.get_token(), game.get_pot().0), (Some(TOKEN.to_string()), 200));
+
+        // Ending the game doesn't send the tokens, the winner claims them.
+        testing_env!(get_account_context(BLACK, vec![], false));
+        assert_eq!(contract.resign(game_id), "White wins by resignation.");
+        assert_eq!(contract.get_game_status(game_id).get_pot().0, 0);
+        assert_eq!(contract.get_unpaid(WHITE.to_string(), TOKEN.to_string()).0, 200);
+
+        testing_env!(get_context(vec![], false));
+        assert_eq!(contract.claim_unpaid(TOKEN.to_string()).0, 200);
+        assert_eq!(contract.get_unpaid(WHITE.to_string(), TOKEN.to_string()).0, 0);
+
+        // If the ft_transfer fails, the winner can claim it again.
+        let context = get_account_context("alice.testnet", vec![], false);
+        test_utils::testing_env_with_promise_results(context, PromiseResult::Failed);
+        contract.on_token_payout(WHITE.to_string(), TOKEN.to_string(), U128(200));
+        assert_eq!(contract.get_unpaid(WHITE.to_string(), TOKEN.to_string()).0, 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attach at least 30000000000000 gas")]
+    fn claim_unpaid_without_gas() {
+        let mut contract = new_contract();
+
+        let mut context: VMContext = get_context(vec![], false);
+        context.prepaid_gas = 10u64.pow(13);
+        testing_env!(context);
+        contract.claim_unpaid("token.testnet".to_string());
+    }
+
+    #[test]
+    fn ratings() {
+        let mut contract = new_contract();
+
+        testing_env!(get_context(vec![], false));
+        let challenge_id: ChallengeId = contract.create_challenge(None, ColorPreference::White, None, true);
+        testing_env!(get_account_context(BLACK, vec![], false));
+        let game_id: GameId = contract.accept_challenge(challenge_id);
+        assert!(contract.get_game_status(game_id).is_rated());
+        assert_eq!(contract.reset_game(game_id, WHITE.to_string()), "Rated games can't be reset.");
+
+        // Even ratings, so the winner gets half the K-factor of a new player.
+        assert_eq!(contract.resign(game_id), "White wins by resignation.");
+        let logs: Vec<String> = test_utils::get_logs();
+        assert_eq!(
+            logs.last().unwrap(),
+            "EVENT_JSON:{\"standard\":\"chess\",\"version\":\"1.0.0\",\"event\":\"ratings_updated\",\"data\":[{\"game_id\":\"1\",\"white\":{\"player\":\"jane.testnet\",\"rating\":1520,\"games\":1},\"black\":{\"player\":\"bob.testnet\",\"rating\":1480,\"games\":1}}]}",
+        );
+        assert_eq!((contract.get_rating(WHITE.to_string()).get_rating(), contract.get_rating(BLACK.to_string()).get_rating()), (1520, 1480));
+
+        // The favourite gains less for a draw than the underdog does.
+        testing_env!(get_context(vec![], false));
+        let challenge_id: ChallengeId = contract.create_challenge(None, ColorPreference::White, None, true);
+        testing_env!(get_account_context(BLACK, vec![], false));
+        let game_id: GameId = contract.accept_challenge(challenge_id);
+        assert_eq!(contract.offer_draw(game_id), "Draw offered.");
+        testing_env!(get_context(vec![], false));
+        assert_eq!(contract.accept_draw(game_id), "Draw by agreement.");
+        assert_eq!((contract.get_rating(WHITE.to_string()).get_rating(), contract.get_rating(BLACK.to_string()).get_rating()), (1518, 1482));
+        assert_eq!(contract.get_rating(BLACK.to_string()).get_games(), 2);
+
+        // Unrated games don't change them.
+        assert_eq!(contract.resign(GAME), "Black wins by resignation.");
+        assert_eq!(contract.get_rating(WHITE.to_string()).get_rating(), 1518);
+    }
+}
@@ -1,2 +1,3 @@
+pub mod castling;
 pub mod report;
 pub mod step;
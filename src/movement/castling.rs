@@ -0,0 +1,95 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::pieces::piece::Color;
+
+
+// Index of each square that matters for castling.
+//
+// 0 - - - 4 - - 7      black rooks and king
+// ...
+// 56- - - 60- - 63     white rooks and king
+const BLACK_QUEEN_SIDE_ROOK: u8 = 0;
+const BLACK_KING: u8 = 4;
+const BLACK_KING_SIDE_ROOK: u8 = 7;
+const WHITE_QUEEN_SIDE_ROOK: u8 = 56;
+const WHITE_KING: u8 = 60;
+const WHITE_KING_SIDE_ROOK: u8 = 63;
+
+
+/// Which castling moves each player is still allowed to do.
+/// A right is lost for good once the king or the matching rook leaves (or is captured in) its starting square.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CastlingRights{
+    white_king_side: bool,
+    white_queen_side: bool,
+    black_king_side: bool,
+    black_queen_side: bool,
+}
+
+impl Default for CastlingRights{
+    // At the start of the game every castling move is still available.
+    fn default() -> Self{
+        CastlingRights{
+            white_king_side: true,
+            white_queen_side: true,
+            black_king_side: true,
+            black_queen_side: true,
+        }
+    }
+}
+
+impl CastlingRights{
+    pub fn new(
+        white_king_side: bool,
+        white_queen_side: bool,
+        black_king_side: bool,
+        black_queen_side: bool,
+    ) -> Self {
+        CastlingRights{
+            white_king_side,
+            white_queen_side,
+            black_king_side,
+            black_queen_side,
+        }
+    }
+
+    pub fn can_castle(&self, color: &Color, king_side: bool) -> bool {
+        match (color, king_side) {
+            (Color::WHITE, true) => self.white_king_side,
+            (Color::WHITE, false) => self.white_queen_side,
+            (Color::BLACK, true) => self.black_king_side,
+            (Color::BLACK, false) => self.black_queen_side,
+            (Color::EMPTY, _) => panic!("Error in CastlingRights.can_castle. Color is Empty."),
+        }
+    }
+
+    /// All four rights as bits of a single byte. Used to compare positions.
+    pub fn get_key(&self) -> u8 {
+        (self.white_king_side as u8)
+        | ((self.white_queen_side as u8) << 1)
+        | ((self.black_king_side as u8) << 2)
+        | ((self.black_queen_side as u8) << 3)
+    }
+
+    /// Called with the start and end index of every committed move.
+    /// If anything moves from or into one of the starting squares above, the matching rights are gone.
+    pub fn remove_rights_at(&mut self, index: u8) {
+        match index {
+            BLACK_QUEEN_SIDE_ROOK => { self.black_queen_side = false; },
+            BLACK_KING_SIDE_ROOK => { self.black_king_side = false; },
+            BLACK_KING => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            },
+            WHITE_QUEEN_SIDE_ROOK => { self.white_queen_side = false; },
+            WHITE_KING_SIDE_ROOK => { self.white_king_side = false; },
+            WHITE_KING => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            },
+            _ => {},
+        }
+    }
+}
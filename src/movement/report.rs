@@ -145,6 +145,10 @@ impl Report{
         false
     }
 
+    pub fn get_steps(&self) -> &Vec<Step> {
+        &self.steps
+    }
+
     pub fn can_avoid_checkmate(
         &mut self,
         board: &mut Board,
@@ -1,11 +1,13 @@
 use crate::{
     board::Board,
+    movement::report::Report,
     pieces::{
         piece::{
             Color,
             Piece,
         },
         piece_ext::PieceExt,
+        promotion::Promotion,
     },
     player::player::Player,
 };
@@ -18,6 +20,8 @@ pub struct Step{
     pub current_piece: Piece,
     pub target_piece: Piece,
     pub moved: bool,
+    // What a pawn reaching the last row becomes. Not compared in eq.
+    pub promotion: Option<Promotion>,
 }
 
 impl PartialEq for Step{
@@ -72,6 +76,7 @@ impl Step{
             current_piece: current,
             target_piece: target,
             moved: false,
+            promotion: None,
         }
     }
 
@@ -92,6 +97,121 @@ impl Step{
             current_piece,
             target_piece,
             moved: false,
+            promotion: None,
+        }
+    }
+
+    pub fn set_promotion(&mut self, promotion: Option<Promotion>) {
+        self.promotion = promotion;
+    }
+
+    /// A pawn reaching the last row of the board must be promoted.
+    pub fn is_promotion(&self) -> bool {
+        let end_row: u8 = self.target_piece.get_row();
+
+        match self.current_piece {
+            Piece::PAWN(_) => (end_row == 0) || (end_row == 7),
+            _ => false,
+        }
+    }
+
+    /// The piece number that ends up in the target position.
+    /// Same as the current piece, unless it's a promoted pawn.
+    fn get_final_piece_number(&self) -> u8 {
+        match (self.is_promotion(), &self.promotion) {
+            (true, Some(promotion)) => promotion.get_piece_number(
+                &self.current_piece.get_color(),// color: &Color,
+            ),
+            (_, _) => self.current_piece.get_piece_number(),
+        }
+    }
+
+    /// True if a rival piece is removed from the board with this step.
+    pub fn is_capture(&self) -> bool {
+        match self.target_piece {
+            Piece::EMPTY(_) => self.is_en_passant(),
+            _ => true,
+        }
+    }
+
+    /// Piece number of the rival piece removed with this step, 0 if none.
+    pub fn get_captured_piece_number(&self) -> u8 {
+        match self.is_en_passant() {
+            true => self.get_en_passant_pawn(),
+            false => self.target_piece.get_piece_number(),
+        }
+    }
+
+    pub fn is_pawn_move(&self) -> bool {
+        matches!(self.current_piece, Piece::PAWN(_))
+    }
+
+    /// Castling is the only move where the king walks two columns.
+    pub fn is_castling(&self) -> bool {
+        let start_col: i8 = self.current_piece.get_column() as i8;
+        let end_col: i8 = self.target_piece.get_column() as i8;
+
+        self.current_piece.is_king() && ((start_col - end_col).abs() == 2)
+    }
+
+    /// Returns (rook piece number, rook starting column, rook ending column) for a castling step.
+    fn get_castling_rook(&self) -> (u8, u8, u8) {
+        let rook_number: u8 = match self.current_piece.get_color() {
+            Color::EMPTY => panic!("Error in Step.get_castling_rook. Current color is Empty."),
+            Color::BLACK => 8,
+            Color::WHITE => 2,
+        };
+
+        match self.target_piece.get_column() {
+            // King side
+            6 => (rook_number, 7, 5),
+            // Queen side
+            _ => (rook_number, 0, 3),
+        }
+    }
+
+    /// A pawn moving diagonally into an empty position is capturing en passant.
+    pub fn is_en_passant(&self) -> bool {
+        let start_col: u8 = self.current_piece.get_column();
+        let end_col: u8 = self.target_piece.get_column();
+
+        match (&self.current_piece, &self.target_piece) {
+            (Piece::PAWN(_), Piece::EMPTY(_)) => start_col != end_col,
+            (_, _) => false,
+        }
+    }
+
+    /// Returns the piece number of the pawn captured en passant.
+    /// It stands beside the starting position, in the column of the target.
+    fn get_en_passant_pawn(&self) -> u8 {
+        match self.current_piece.get_color() {
+            Color::EMPTY => panic!("Error in Step.get_en_passant_pawn. Current color is Empty."),
+            Color::BLACK => 1,
+            Color::WHITE => 7,
+        }
+    }
+
+    /// If a pawn takes two steps, returns the index of the position it skipped.
+    fn get_skipped_position(&self) -> Option<u8> {
+        let start_row: u8 = self.current_piece.get_row();
+        let end_row: u8 = self.target_piece.get_row();
+
+        match self.current_piece {
+            Piece::PAWN(_) if (start_row as i8 - end_row as i8).abs() == 2 => {
+                Some(Report::col_row_to_index(
+                    self.current_piece.get_column(),// col: u8,
+                    (start_row + end_row) / 2,      // row: u8,
+                ))
+            },
+            _ => None,
+        }
+    }
+
+    fn get_rival_color(&self) -> Color {
+        match self.current_piece.get_color() {
+            Color::EMPTY => panic!("Error in Step.get_rival_color. Current_color is Empty."),
+            Color::BLACK => Color::WHITE,
+            Color::WHITE => Color::BLACK,
         }
     }
 
@@ -101,7 +221,7 @@ impl Step{
     ) {
         let start_col: u8 = self.current_piece.get_column();
         let start_row: u8 = self.current_piece.get_row();
-        let piece_number: u8 = self.current_piece.get_piece_number();
+        let piece_number: u8 = self.get_final_piece_number();
         let end_col: u8 = self.target_piece.get_column();
         let end_row: u8 = self.target_piece.get_row();
 
@@ -119,6 +239,32 @@ impl Step{
             end_row,                        // row: u8,
         );
 
+        // When castling, the rook jumps to the other side of the king.
+        if self.is_castling() {
+            let (rook_number, rook_start_col, rook_end_col): (u8, u8, u8) = self.get_castling_rook();
+
+            board.replace_piece(
+                0,                          // piece_number: u8,
+                rook_start_col,             // col: u8,
+                start_row,                  // row: u8,
+            );
+
+            board.replace_piece(
+                rook_number,                // piece_number: u8,
+                rook_end_col,               // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
+        // When capturing en passant, the rival pawn is not in the target position.
+        if self.is_en_passant() {
+            board.replace_piece(
+                0,                          // piece_number: u8,
+                end_col,                    // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
         // Step has concluded.
         self.moved = true;
     }
@@ -149,6 +295,32 @@ impl Step{
             end_row,                        // row: u8,
         );
 
+        // Put the castling rook back in its corner.
+        if self.is_castling() {
+            let (rook_number, rook_start_col, rook_end_col): (u8, u8, u8) = self.get_castling_rook();
+
+            board.replace_piece(
+                0,                          // piece_number: u8,
+                rook_end_col,               // col: u8,
+                start_row,                  // row: u8,
+            );
+
+            board.replace_piece(
+                rook_number,                // piece_number: u8,
+                rook_start_col,             // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
+        // Put the pawn captured en passant back.
+        if self.is_en_passant() {
+            board.replace_piece(
+                self.get_en_passant_pawn(), // piece_number: u8,
+                end_col,                    // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
         // Step is still to be concluded.
         self.moved = false;
     }
@@ -161,7 +333,7 @@ impl Step{
     ){
         let start_col: u8 = self.current_piece.get_column();
         let start_row: u8 = self.current_piece.get_row();
-        let piece_number: u8 = self.current_piece.get_piece_number();
+        let piece_number: u8 = self.get_final_piece_number();
         let end_col: u8 = self.target_piece.get_column();
         let end_row: u8 = self.target_piece.get_row();
 
@@ -176,6 +348,44 @@ impl Step{
             end_col,                        // col: u8, 
             end_row,                        // row: u8,
         );
+
+        if self.is_castling() {
+            let (rook_number, rook_start_col, rook_end_col): (u8, u8, u8) = self.get_castling_rook();
+
+            board.finalize_movement(
+                0,                          // piece_number: u8,
+                rook_start_col,             // col: u8,
+                start_row,                  // row: u8,
+            );
+
+            board.finalize_movement(
+                rook_number,                // piece_number: u8,
+                rook_end_col,               // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
+        if self.is_en_passant() {
+            board.finalize_movement(
+                0,                          // piece_number: u8,
+                end_col,                    // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
+        // En passant is only available in the turn right after a pawn takes two steps.
+        board.set_en_passant(
+            self.get_skipped_position(),
+        );
+
+        // Anything moving from or into a king/rook starting square clears the matching castling rights.
+        board.remove_castling_rights(
+            Report::col_row_to_index(start_col, start_row),
+        );
+
+        board.remove_castling_rights(
+            Report::col_row_to_index(end_col, end_row),
+        );
     }
 
     pub fn revert_finalize(
@@ -202,17 +412,72 @@ impl Step{
             end_row,                        // row: u8,
         );
 
+        if self.is_castling() {
+            let (rook_number, rook_start_col, rook_end_col): (u8, u8, u8) = self.get_castling_rook();
+
+            board.finalize_movement(
+                0,                          // piece_number: u8,
+                rook_end_col,               // col: u8,
+                start_row,                  // row: u8,
+            );
+
+            board.finalize_movement(
+                rook_number,                // piece_number: u8,
+                rook_start_col,             // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+
+        if self.is_en_passant() {
+            board.finalize_movement(
+                self.get_en_passant_pawn(), // piece_number: u8,
+                end_col,                    // col: u8,
+                start_row,                  // row: u8,
+            );
+        }
+    }
+
+    /// The king can't castle out of check, or walk through a position under attack.
+    /// Landing in check is verified like any other step.
+    fn is_castling_path_safe(&self, board: &mut Board) -> bool {
+        let rival_color: Color = self.get_rival_color();
+
+        let mut rival_player: Player = Player::new(
+            rival_color,                    // color: Color,
+            board,                          // board: &Board,
+        );
+
+        // Is the king under check before moving?
+        rival_player.build_reports(
+            board,                          // board: &Board,
+            true,                           // search_checkmate: bool,
+        );
+
+        if rival_player.is_check() {
+            return false;
+        }
+
+        let row: u8 = self.current_piece.get_row();
+        let passing_col: u8 = (self.current_piece.get_column() + self.target_piece.get_column()) / 2;
+
+        // Is the king under check in the position it walks through?
+        let mut passing_step: Step = Step::new(
+            self.current_piece,     // current: Piece,
+            board.get_piece(passing_col, row),// target: Piece,
+        );
+
+        passing_step.can_avoid_checkmate(
+            board,                          // board: &mut Board,
+        )
     }
 
     /// Build a rival player and see if current player is under check after this move.
     pub fn can_avoid_checkmate(&mut self, board: &mut Board) -> bool {
-        let current_color: Color = self.current_piece.get_color();
+        let rival_color: Color = self.get_rival_color();
 
-        let rival_color: Color = match current_color {
-            Color::EMPTY => panic!("Error in Step.can_avoid_checkmate. Current_color is Empty."),
-            Color::BLACK => Color::WHITE,
-            Color::WHITE => Color::BLACK,
-        };
+        if self.is_castling() && !self.is_castling_path_safe(board) {
+            return false;
+        }
 
         self.commit_move(
             board,                          // board: &mut Board,
@@ -0,0 +1,359 @@
+use crate::{
+    board::Board,
+    movement::{
+        castling::CastlingRights,
+        report::Report,
+    },
+    pieces::piece::Color,
+    player::player::Player,
+};
+
+
+/// Everything a FEN string says about a position, in the same format Game stores it.
+///
+/// A FEN has 6 fields separated by spaces:
+/// "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+///  - pieces, row by row from black's side. Numbers count empty positions;
+///  - who moves next, "w" or "b";
+///  - castling rights, or "-";
+///  - en passant position, or "-";
+///  - halfmove clock;
+///  - fullmove number, starting at 1 and going up after each black move.
+pub struct FenPosition{
+    pub squares: [u8; 64],
+    // false: White, true: Black
+    pub player_turn: bool,
+    pub castling: CastlingRights,
+    pub en_passant: Option<u8>,
+    pub halfmove_clock: u8,
+    // How many turns the game completed before this position, from the fullmove number. Like Game.start_turn.
+    pub turn: u32,
+}
+
+/// Why a FEN string was rejected.
+pub enum FenError{
+    // There must be exactly 6 fields separated by spaces.
+    FieldCount,
+    // There must be 8 rows of 8 positions, with only valid piece letters.
+    InvalidBoard,
+    // Each player must have exactly one king.
+    InvalidKings,
+    // Pawns can't be in the first or last row.
+    InvalidPawns,
+    // Player must be "w" or "b".
+    InvalidPlayer,
+    // Castling must be "-" or letters from "KQkq", with the king and rook still in their starting positions.
+    InvalidCastling,
+    // En passant must be "-" or the position behind a pawn that just took two steps.
+    InvalidEnPassant,
+    // The clocks must be numbers the game can hold (halfmove below 150, fullmove from 1 to 65535).
+    InvalidClock,
+    // The player that just moved can't have left their king under check.
+    RivalInCheck,
+}
+
+impl FenError{
+    pub fn get_message(&self) -> &str {
+        match self {
+            FenError::FieldCount => "FEN must have 6 fields separated by spaces.",
+            FenError::InvalidBoard => "FEN board must have 8 rows of 8 positions, with pieces from \"PRNBQKprnbqk\".",
+            FenError::InvalidKings => "Each player must have exactly one king.",
+            FenError::InvalidPawns => "Pawns can't be in the first or last row.",
+            FenError::InvalidPlayer => "Player to move must be \"w\" or \"b\".",
+            FenError::InvalidCastling => "Castling must be \"-\" or letters from \"KQkq\", with the king and rook in their starting positions.",
+            FenError::InvalidEnPassant => "En passant must be \"-\" or the position a pawn skipped in the last move.",
+            FenError::InvalidClock => "Halfmove clock must be lower than 150 and fullmove number from 1 to 65535.",
+            FenError::RivalInCheck => "The player that just moved can't be under check.",
+        }
+    }
+}
+
+
+fn get_piece_char(piece_number: u8) -> char {
+    match piece_number {
+        1 => 'P', 2 => 'R', 3 => 'N', 4 => 'B', 5 => 'Q', 6 => 'K',
+        7 => 'p', 8 => 'r', 9 => 'n', 10 => 'b', 11 => 'q', 12 => 'k',
+        _ => panic!("Error in fen.get_piece_char. Invalid piece number ({}).", piece_number),
+    }
+}
+
+fn get_piece_number(piece_char: char) -> Option<u8> {
+    match piece_char {
+        'P' => Some(1), 'R' => Some(2), 'N' => Some(3), 'B' => Some(4), 'Q' => Some(5), 'K' => Some(6),
+        'p' => Some(7), 'r' => Some(8), 'n' => Some(9), 'b' => Some(10), 'q' => Some(11), 'k' => Some(12),
+        _ => None,
+    }
+}
+
+/// Name of the position in algebraic notation. Index 0 is "a8", index 63 is "h1".
+pub fn get_square_name(index: u8) -> String {
+    let col: u8 = index % 8;
+    let row: u8 = index / 8;
+
+    format!("{}{}", (b'a' + col) as char, 8 - row)
+}
+
+/// Index of a position in algebraic notation, like "e4".
+pub fn get_square_index(name: &str) -> Option<u8> {
+    let bytes: &[u8] = name.as_bytes();
+    if bytes.len() != 2 {
+        return None;
+    }
+
+    let (file, rank): (u8, u8) = (bytes[0], bytes[1]);
+    if !(b'a'..=b'h').contains(&file) || !(b'1'..=b'8').contains(&rank) {
+        return None;
+    }
+
+    Some(Report::col_row_to_index(
+        file - b'a',                        // col: u8,
+        7 - (rank - b'1'),                  // row: u8,
+    ))
+}
+
+
+/// Write a position as FEN.
+pub fn get_fen(
+    squares: &[u8],
+    player_turn: bool,
+    castling: &CastlingRights,
+    en_passant: Option<u8>,
+    halfmove_clock: u8,
+    turn: u32,
+) -> String {
+    let mut rows: Vec<String> = Vec::with_capacity(8);
+
+    for row in 0..8 {
+        let mut row_string: String = String::new();
+        let mut empty_count: u8 = 0;
+
+        for col in 0..8 {
+            let piece_number: u8 = squares[row * 8 + col];
+            if piece_number == 0 {
+                empty_count += 1;
+                continue;
+            }
+
+            if empty_count > 0 {
+                row_string.push((b'0' + empty_count) as char);
+                empty_count = 0;
+            }
+            row_string.push(get_piece_char(piece_number));
+        }
+
+        if empty_count > 0 {
+            row_string.push((b'0' + empty_count) as char);
+        }
+        rows.push(row_string);
+    }
+
+    let player: &str = match player_turn {
+        false => "w",
+        true => "b",
+    };
+
+    let mut castling_string: String = String::new();
+    for (color, king_side, letter) in [
+        (Color::WHITE, true, 'K'),
+        (Color::WHITE, false, 'Q'),
+        (Color::BLACK, true, 'k'),
+        (Color::BLACK, false, 'q'),
+    ] {
+        if castling.can_castle(&color, king_side) {
+            castling_string.push(letter);
+        }
+    }
+    if castling_string.is_empty() {
+        castling_string.push('-');
+    }
+
+    let en_passant_string: String = match en_passant {
+        None => String::from("-"),
+        Some(index) => get_square_name(index),
+    };
+
+    // Fullmove number goes up after each black move.
+    let fullmove_number: u32 = turn / 2 + 1;
+
+    format!("{} {} {} {} {} {}",
+        rows.join("/"),
+        player,
+        castling_string,
+        en_passant_string,
+        halfmove_clock,
+        fullmove_number,
+    )
+}
+
+
+fn parse_board(field: &str) -> Result<[u8; 64], FenError> {
+    let mut squares: [u8; 64] = [0; 64];
+
+    let rows: Vec<&str> = field.split('/').collect();
+    if rows.len() != 8 {
+        return Result::Err(FenError::InvalidBoard);
+    }
+
+    for (row, row_string) in rows.iter().enumerate() {
+        let mut col: usize = 0;
+
+        for piece_char in row_string.chars() {
+            if let Some(empty_count) = piece_char.to_digit(10) {
+                if (empty_count == 0) || (empty_count > 8) {
+                    return Result::Err(FenError::InvalidBoard);
+                }
+                col += empty_count as usize;
+                continue;
+            }
+
+            let piece_number: u8 = match get_piece_number(piece_char) {
+                None => return Result::Err(FenError::InvalidBoard),
+                Some(piece_number) => piece_number,
+            };
+
+            if col >= 8 {
+                return Result::Err(FenError::InvalidBoard);
+            }
+            squares[row * 8 + col] = piece_number;
+            col += 1;
+        }
+
+        if col != 8 {
+            return Result::Err(FenError::InvalidBoard);
+        }
+    }
+
+    let white_kings: usize = squares.iter().filter(|piece_number| **piece_number == 6).count();
+    let black_kings: usize = squares.iter().filter(|piece_number| **piece_number == 12).count();
+    if (white_kings != 1) || (black_kings != 1) {
+        return Result::Err(FenError::InvalidKings);
+    }
+
+    for index in (0..8).chain(56..64) {
+        if (squares[index] == 1) || (squares[index] == 7) {
+            return Result::Err(FenError::InvalidPawns);
+        }
+    }
+
+    Result::Ok(squares)
+}
+
+fn parse_castling(field: &str, squares: &[u8; 64]) -> Result<CastlingRights, FenError> {
+    if field == "-" {
+        return Result::Ok(CastlingRights::new(false, false, false, false));
+    }
+
+    let (mut white_king_side, mut white_queen_side, mut black_king_side, mut black_queen_side) = (false, false, false, false);
+
+    for letter in field.chars() {
+        // (right, king index, king number, rook index, rook number)
+        let (right, king, king_number, rook, rook_number): (&mut bool, usize, u8, usize, u8) = match letter {
+            'K' => (&mut white_king_side, 60, 6, 63, 2),
+            'Q' => (&mut white_queen_side, 60, 6, 56, 2),
+            'k' => (&mut black_king_side, 4, 12, 7, 8),
+            'q' => (&mut black_queen_side, 4, 12, 0, 8),
+            _ => return Result::Err(FenError::InvalidCastling),
+        };
+
+        // Each letter only once, and the pieces must not have moved.
+        if *right || (squares[king] != king_number) || (squares[rook] != rook_number) {
+            return Result::Err(FenError::InvalidCastling);
+        }
+        *right = true;
+    }
+
+    Result::Ok(CastlingRights::new(
+        white_king_side,                    // white_king_side: bool,
+        white_queen_side,                   // white_queen_side: bool,
+        black_king_side,                    // black_king_side: bool,
+        black_queen_side,                   // black_queen_side: bool,
+    ))
+}
+
+fn parse_en_passant(field: &str, squares: &[u8; 64], player_turn: bool) -> Result<Option<u8>, FenError> {
+    if field == "-" {
+        return Result::Ok(None);
+    }
+
+    let index: u8 = match get_square_index(field) {
+        None => return Result::Err(FenError::InvalidEnPassant),
+        Some(index) => index,
+    };
+
+    let col: u8 = index % 8;
+    // (row skipped, row the pawn started, row the pawn is now, pawn number)
+    let (skipped_row, start_row, pawn_row, pawn_number): (u8, u8, u8, u8) = match player_turn {
+        // Black just moved.
+        false => (2, 1, 3, 7),
+        // White just moved.
+        true => (5, 6, 4, 1),
+    };
+
+    if (index / 8 != skipped_row)
+        || (squares[Report::col_row_to_index(col, pawn_row) as usize] != pawn_number)
+        || (squares[Report::col_row_to_index(col, skipped_row) as usize] != 0)
+        || (squares[Report::col_row_to_index(col, start_row) as usize] != 0)
+    {
+        return Result::Err(FenError::InvalidEnPassant);
+    }
+
+    Result::Ok(Some(index))
+}
+
+/// Read a FEN string, checking that the position is one the game can continue from.
+pub fn parse_fen(fen: &str) -> Result<FenPosition, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Result::Err(FenError::FieldCount);
+    }
+
+    let squares: [u8; 64] = parse_board(fields[0])?;
+
+    let player_turn: bool = match fields[1] {
+        "w" => false,
+        "b" => true,
+        _ => return Result::Err(FenError::InvalidPlayer),
+    };
+
+    let castling: CastlingRights = parse_castling(fields[2], &squares)?;
+    let en_passant: Option<u8> = parse_en_passant(fields[3], &squares, player_turn)?;
+
+    let halfmove_clock: u8 = match fields[4].parse::<u8>() {
+        Result::Ok(value) if value < 150 => value,
+        _ => return Result::Err(FenError::InvalidClock),
+    };
+
+    let fullmove_number: u16 = match fields[5].parse::<u16>() {
+        Result::Ok(value) if value > 0 => value,
+        _ => return Result::Err(FenError::InvalidClock),
+    };
+
+    // u32, so a big fullmove number can't overflow.
+    let turn: u32 = (fullmove_number as u32 - 1) * 2 + (player_turn as u32);
+
+    // If the player to move can reach the rival king, the rival left it under check.
+    let board: Board = Board::new(squares);
+    let mut current_player: Player = Player::new(
+        match player_turn {
+            false => Color::WHITE,
+            true => Color::BLACK,
+        },                                  // color: Color,
+        &board,                             // board: &Board,
+    );
+    current_player.build_reports(
+        &board,                             // board: &Board,
+        false,                              // search_checkmate: bool,
+    );
+    if current_player.is_check() {
+        return Result::Err(FenError::RivalInCheck);
+    }
+
+    Result::Ok(FenPosition{
+        squares,
+        player_turn,
+        castling,
+        en_passant,
+        halfmove_clock,
+        turn,
+    })
+}
@@ -0,0 +1,167 @@
+use crate::{
+    game::{
+        history::MoveRecord,
+        Game,
+        GameId,
+    },
+};
+
+
+// PGN lines shouldn't be longer than this.
+const MAX_LINE_LENGTH: usize = 80;
+
+
+/// "YYYY.MM.DD" for a block timestamp in nanoseconds.
+pub fn get_date(timestamp: u64) -> String {
+    let days: i64 = (timestamp / 1_000_000_000 / 86_400) as i64;
+
+    // Days since 1970-01-01 to a civil date, counting eras of 400 years from March 1st.
+    let z: i64 = days + 719_468;
+    let era: i64 = z / 146_097;
+    let day_of_era: i64 = z - era * 146_097;
+    let year_of_era: i64 = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index: i64 = (5 * day_of_year + 2) / 153;
+
+    let day: i64 = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month: i64 = match month_index < 10 {
+        true => month_index + 3,
+        false => month_index - 9,
+    };
+    let year: i64 = year_of_era + era * 400 + ((month <= 2) as i64);
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// The whole game in PGN: the Seven Tag Roster, then every move in SAN.
+/// site is the account of the contract.
+pub fn get_pgn(game: &Game, game_id: GameId, site: &str) -> String {
+    let result: String = String::from(game.get_result().get_pgn_result());
+
+    let mut pgn: String = format!(
+        "[Event \"Chess game {}\"]\n[Site \"{}\"]\n[Date \"{}\"]\n[Round \"-\"]\n[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n",
+        game_id.0,
+        site,
+        get_date(game.get_created_at().0),
+        game.get_white_player(),
+        game.get_black_player(),
+        result,
+    );
+
+    // A game that didn't start from the usual position must say where it started.
+    if let Some(fen) = game.get_start_fen() {
+        pgn = format!("{}[SetUp \"1\"]\n[FEN \"{}\"]\n", pgn, fen);
+    }
+
+    let history: Vec<MoveRecord> = game.get_history_game(0, game.get_history_length_game());
+
+    // Turn of the first move, so numbering works for games from FEN too.
+    let first_turn: u32 = game.get_start_turn();
+
+    let mut tokens: Vec<String> = Vec::with_capacity(history.len() * 2 + 1);
+    for (turn, (index, record)) in (first_turn..).zip(history.iter().enumerate()) {
+        let move_number: u32 = turn / 2 + 1;
+
+        match (turn % 2, index) {
+            // White moves start a new number.
+            (0, _) => tokens.push(format!("{}.", move_number)),
+            // A game starting with black's move.
+            (_, 0) => tokens.push(format!("{}...", move_number)),
+            (_, _) => {},
+        }
+
+        tokens.push(record.get_san());
+    }
+    tokens.push(result);
+
+    // Break the move text into lines, without splitting any token.
+    let mut line: String = String::new();
+    let mut move_text: String = String::new();
+    for token in tokens {
+        if !line.is_empty() && (line.len() + 1 + token.len() > MAX_LINE_LENGTH) {
+            move_text = format!("{}{}\n", move_text, line);
+            line.clear();
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    move_text = format!("{}{}\n", move_text, line);
+
+    format!("{}\n{}", pgn, move_text)
+}
+
+
+/// Value of a tag pair like [FEN "..."], if the PGN has it.
+pub fn get_tag(pgn: &str, name: &str) -> Option<String> {
+    let start: String = format!("[{} \"", name);
+
+    for line in pgn.lines() {
+        let line: &str = line.trim();
+        if line.starts_with(&start) && line.ends_with("\"]") && (line.len() >= start.len() + 2) {
+            return Some(String::from(&line[start.len()..line.len() - 2]));
+        }
+    }
+
+    None
+}
+
+/// Each move in SAN from the move text of a PGN.
+/// Tags, comments, variations, move numbers, annotations ($1) and the result are left out.
+pub fn get_san_moves(pgn: &str) -> Vec<String> {
+    let mut move_text: String = String::new();
+
+    // Nothing inside tags, {comments}, ;comments or (variations) matters.
+    let mut in_tag: bool = false;
+    let mut in_comment: bool = false;
+    let mut in_line_comment: bool = false;
+    let mut variation_depth: u32 = 0;
+
+    for letter in pgn.chars() {
+        match letter {
+            _ if in_tag => { in_tag = letter != ']'; },
+            _ if in_comment => { in_comment = letter != '}'; },
+            _ if in_line_comment => { in_line_comment = letter != '\n'; },
+            '(' => { variation_depth += 1; },
+            ')' if variation_depth > 0 => { variation_depth -= 1; },
+            _ if variation_depth > 0 => {},
+            '[' => { in_tag = true; },
+            '{' => { in_comment = true; },
+            ';' => { in_line_comment = true; },
+            _ => { move_text.push(letter); },
+        }
+        if in_tag || in_comment || in_line_comment || (variation_depth > 0) {
+            // Keep the tokens around it apart.
+            move_text.push(' ');
+        }
+    }
+
+    let mut moves: Vec<String> = Vec::new();
+
+    for token in move_text.split_whitespace() {
+        match token {
+            // Results, and the en passant annotation when it's apart from the move.
+            "*" | "1-0" | "0-1" | "1/2-1/2" | "e.p." => continue,
+            _ if token.starts_with('$') => continue,
+            _ => {},
+        }
+
+        // "12." or "12..." may be glued to the move, like "12.e4". "0-0" has no dot, so it stays.
+        let number_end: usize = token
+            .find(|letter: char| !letter.is_ascii_digit() && (letter != '.'))
+            .unwrap_or(token.len());
+        let token: &str = match token[..number_end].contains('.') {
+            true => &token[number_end..],
+            false => token,
+        };
+
+        if !token.is_empty() {
+            moves.push(String::from(token));
+        }
+    }
+
+    moves
+}
+
@@ -0,0 +1,305 @@
+use crate::{
+    board::Board,
+    movement::{
+        report::Report,
+        step::Step,
+    },
+    notation::fen::get_square_name,
+    pieces::{
+        piece::Piece,
+        piece_ext::PieceExt,
+        promotion::Promotion,
+    },
+};
+
+
+/// Letter used for each piece in algebraic notation. Pawns don't have one.
+pub fn get_piece_letter(piece: &Piece) -> &str {
+    match piece {
+        Piece::ROOK(_) => "R",
+        Piece::KNIGHT(_) => "N",
+        Piece::BISHOP(_) => "B",
+        Piece::QUEEN(_) => "Q",
+        Piece::KING(_) => "K",
+        _ => "",
+    }
+}
+
+pub fn get_promotion_letter(promotion: &Promotion) -> &str {
+    match promotion {
+        Promotion::QUEEN => "Q",
+        Promotion::ROOK => "R",
+        Promotion::BISHOP => "B",
+        Promotion::KNIGHT => "N",
+    }
+}
+
+/// "+" for check, "#" for checkmate.
+pub fn get_check_suffix(check: bool, checkmate: bool) -> &'static str {
+    match (check, checkmate) {
+        (_, true) => "#",
+        (true, false) => "+",
+        (false, false) => "",
+    }
+}
+
+/// Indexes of the other pieces of the same kind that could also go to the target of step.
+fn get_others_for_target(board: &mut Board, step: &Step) -> Vec<u8> {
+    let piece_number: u8 = step.current_piece.get_piece_number();
+    let current: u8 = Report::col_row_to_index(
+        step.current_piece.get_column(),    // col: u8,
+        step.current_piece.get_row(),       // row: u8,
+    );
+    let target: u8 = Report::col_row_to_index(
+        step.target_piece.get_column(),     // col: u8,
+        step.target_piece.get_row(),        // row: u8,
+    );
+
+    let squares: [u8; 64] = board.get_board_array();
+    let mut others: Vec<u8> = Vec::new();
+
+    for index in 0..64 {
+        if (index == current) || (squares[index as usize] != piece_number) {
+            continue;
+        }
+
+        let piece: Piece = board.get_piece_index(index);
+        let report: Report = piece.get_movement_report(
+            board,                          // board: &Board,
+            false,                          // search_checkmate: bool,
+        );
+
+        let mut other_step: Step = Step::new_index(
+            index,                          // current: u8,
+            target,                         // target: u8,
+            board,                          // board: &Board,
+        );
+
+        // Only counts if the other piece could really do it, without leaving the king under check.
+        if report.is_step_here(&other_step) && other_step.can_avoid_checkmate(board) {
+            others.push(index);
+        }
+    }
+
+    others
+}
+
+/// Standard Algebraic Notation for step, without the check suffix. Board must be the position before the step.
+pub fn get_san(board: &mut Board, step: &Step) -> String {
+    if step.is_castling() {
+        return match step.target_piece.get_column() {
+            6 => String::from("O-O"),
+            _ => String::from("O-O-O"),
+        };
+    }
+
+    let target: String = get_square_name(Report::col_row_to_index(
+        step.target_piece.get_column(),     // col: u8,
+        step.target_piece.get_row(),        // row: u8,
+    ));
+    let capture: &str = match step.is_capture() {
+        true => "x",
+        false => "",
+    };
+
+    let start_col: u8 = step.current_piece.get_column();
+    let start_row: u8 = step.current_piece.get_row();
+    let file: char = (b'a' + start_col) as char;
+    let rank: char = (b'8' - start_row) as char;
+
+    if step.is_pawn_move() {
+        let promotion: String = match &step.promotion {
+            None => String::new(),
+            Some(promotion) => format!("={}", get_promotion_letter(promotion)),
+        };
+
+        // A pawn capture always starts with the file it came from.
+        return match step.is_capture() {
+            true => format!("{}x{}{}", file, target, promotion),
+            false => format!("{}{}", target, promotion),
+        };
+    }
+
+    // If another piece of the same kind can reach the target, say which one moved.
+    // File first, then rank, then both.
+    let others: Vec<u8> = get_others_for_target(board, step);
+    let same_file: bool = others.iter().any(|index| index % 8 == start_col);
+    let same_rank: bool = others.iter().any(|index| index / 8 == start_row);
+
+    let disambiguation: String = match (others.is_empty(), same_file, same_rank) {
+        (true, _, _) => String::new(),
+        (false, false, _) => format!("{}", file),
+        (false, true, false) => format!("{}", rank),
+        (false, true, true) => format!("{}{}", file, rank),
+    };
+
+    format!("{}{}{}{}",
+        get_piece_letter(&step.current_piece),
+        disambiguation,
+        capture,
+        target,
+    )
+}
+
+/// Remove what doesn't change which move it is: check, annotations and "e.p." (glued or not). "0-0" is the same as "O-O".
+fn normalize(text: &str) -> String {
+    text.trim()
+        .trim_end_matches("e.p.")
+        .trim_end()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .replace('0', "O")
+}
+
+/// What a move written in SAN says about it. Optional parts are only checked when written.
+struct SanMove {
+    // Some(true) for "O-O", Some(false) for "O-O-O". The rest is unused then.
+    castling: Option<bool>,
+    // "" for pawns.
+    piece_letter: String,
+    from_col: Option<u8>,
+    from_row: Option<u8>,
+    // Written with "x". Must agree with the move, so "e4" isn't read as "dxe4".
+    capture: bool,
+    target: u8,
+    promotion: Option<Promotion>,
+}
+
+fn parse_promotion(letter: char) -> Option<Promotion> {
+    match letter {
+        'Q' => Some(Promotion::QUEEN),
+        'R' => Some(Promotion::ROOK),
+        'B' => Some(Promotion::BISHOP),
+        'N' => Some(Promotion::KNIGHT),
+        _ => None,
+    }
+}
+
+/// Read normalized text as piece, disambiguation, capture, target and promotion.
+/// Redundant parts are fine: "Pe4", "Ngf3" and "e8Q" are read like "e4", "Nf3" and "e8=Q".
+/// "x" isn't optional: it must be there for a capture, and only for one.
+fn parse_san(text: &str) -> Option<SanMove> {
+    let castling: Option<bool> = match text {
+        "O-O" => Some(true),
+        "O-O-O" => Some(false),
+        _ => None,
+    };
+    if castling.is_some() {
+        return Some(SanMove{
+            castling,
+            piece_letter: String::new(),
+            from_col: None,
+            from_row: None,
+            capture: false,
+            target: 0,
+            promotion: None,
+        });
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+
+    let promotion: Option<Promotion> = match chars.last() {
+        Some(letter) => parse_promotion(*letter),
+        None => None,
+    };
+    if promotion.is_some() {
+        chars.pop();
+        if chars.last() == Some(&'=') {
+            chars.pop();
+        }
+    }
+
+    // The target square is always last.
+    let rank: char = chars.pop()?;
+    let file: char = chars.pop()?;
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+        return None;
+    }
+    let target: u8 = Report::col_row_to_index(
+        file as u8 - b'a',                  // col: u8,
+        b'8' - rank as u8,                  // row: u8,
+    );
+
+    let capture: bool = chars.last() == Some(&'x');
+    if capture {
+        chars.pop();
+    }
+
+    let piece_letter: String = match chars.first() {
+        Some('P') => {
+            chars.remove(0);
+            String::new()
+        },
+        Some(letter) if "KQRBN".contains(*letter) => String::from(chars.remove(0)),
+        _ => String::new(),
+    };
+
+    let mut from_col: Option<u8> = None;
+    let mut from_row: Option<u8> = None;
+    match chars[..] {
+        [] => {},
+        [file @ 'a'..='h'] => from_col = Some(file as u8 - b'a'),
+        [rank @ '1'..='8'] => from_row = Some(b'8' - rank as u8),
+        [file @ 'a'..='h', rank @ '1'..='8'] => {
+            from_col = Some(file as u8 - b'a');
+            from_row = Some(b'8' - rank as u8);
+        },
+        _ => return None,
+    }
+
+    Some(SanMove{
+        castling,
+        piece_letter,
+        from_col,
+        from_row,
+        capture,
+        target,
+        promotion,
+    })
+}
+
+impl SanMove {
+    fn matches(&self, step: &Step) -> bool {
+        match self.castling {
+            Some(kingside) => return step.is_castling() && ((step.target_piece.get_column() == 6) == kingside),
+            None if step.is_castling() => return false,
+            None => {},
+        }
+
+        let target: u8 = Report::col_row_to_index(
+            step.target_piece.get_column(), // col: u8,
+            step.target_piece.get_row(),    // row: u8,
+        );
+
+        (target == self.target)
+            && (get_piece_letter(&step.current_piece) == self.piece_letter)
+            && self.from_col.is_none_or(|col| col == step.current_piece.get_column())
+            && self.from_row.is_none_or(|row| row == step.current_piece.get_row())
+            && (step.is_capture() == self.capture)
+            // A pawn reaching the last row must say what it becomes, and no other move can.
+            && (step.is_promotion() == self.promotion.is_some())
+    }
+}
+
+/// The step in valid_steps written as text in SAN, with the promotion set from the text.
+/// None if no step matches it, or more than one does (like "Nd2" when both knights can go there).
+pub fn find_step(valid_steps: &[Step], text: &str) -> Option<Step> {
+    let san_move: SanMove = parse_san(&normalize(text))?;
+
+    let mut found: Option<Step> = None;
+    for step in valid_steps {
+        if !san_move.matches(step) {
+            continue;
+        }
+        if found.is_some() {
+            return None;
+        }
+
+        let mut candidate: Step = step.clone();
+        if candidate.is_promotion() {
+            candidate.set_promotion(san_move.promotion);
+        }
+        found = Some(candidate);
+    }
+
+    found
+}
@@ -0,0 +1,49 @@
+use crate::{
+    notation::fen::{
+        get_square_index,
+        get_square_name,
+    },
+    pieces::promotion::Promotion,
+};
+
+
+/// A move in UCI (long algebraic) notation: start and end positions, plus the promotion letter if any.
+/// Like "e2e4", "e1g1" (castling) or "e7e8q".
+pub fn get_uci(current: u8, target: u8, promotion: &Option<Promotion>) -> String {
+    let promotion_letter: &str = match promotion {
+        None => "",
+        Some(Promotion::QUEEN) => "q",
+        Some(Promotion::ROOK) => "r",
+        Some(Promotion::BISHOP) => "b",
+        Some(Promotion::KNIGHT) => "n",
+    };
+
+    format!("{}{}{}",
+        get_square_name(current),
+        get_square_name(target),
+        promotion_letter,
+    )
+}
+
+/// Read a move in UCI notation as (current index, target index, promotion).
+/// None if it isn't written correctly. It doesn't check if the move is valid.
+pub fn parse_uci(text: &str) -> Option<(u8, u8, Option<Promotion>)> {
+    let text: String = text.trim().to_ascii_lowercase();
+    if !text.is_ascii() || (text.len() < 4) || (text.len() > 5) {
+        return None;
+    }
+
+    let current: u8 = get_square_index(&text[0..2])?;
+    let target: u8 = get_square_index(&text[2..4])?;
+
+    let promotion: Option<Promotion> = match &text[4..] {
+        "" => None,
+        "q" => Some(Promotion::QUEEN),
+        "r" => Some(Promotion::ROOK),
+        "b" => Some(Promotion::BISHOP),
+        "n" => Some(Promotion::KNIGHT),
+        _ => return None,
+    };
+
+    Some((current, target, promotion))
+}
@@ -5,5 +5,6 @@ pub mod knight;
 pub mod pawn;
 pub mod piece;
 pub mod piece_ext;
+pub mod promotion;
 pub mod queen;
 pub mod rook;
@@ -20,6 +20,65 @@ pub struct King{
     current_row: u8,
 }
 
+impl King{
+    /// Include the castling step towards one side, if the rights are still there and the path is empty.
+    /// Whether the king is in check, or walks through an attacked square, is verified by Step.can_avoid_checkmate.
+    fn include_castling_step(
+        &self,
+        report: &mut Report,
+        board: &Board,
+        king_side: bool,
+    ){
+        if !board.get_castling_rights().can_castle(&self.color, king_side) {
+            return;
+        }
+
+        // Black on top
+        // White bottom
+        let row: u8 = match self.color{
+            Color::EMPTY => panic!("Error in King.include_castling_step. Own color is Empty."),
+            Color::BLACK => 0,
+            Color::WHITE => 7,
+        };
+
+        if (self.current_row != row) || (self.current_column != 4) {
+            return;
+        }
+
+        let (rook_column, target_column, between): (u8, u8, &[u8]) = match king_side {
+            true => (7, 6, &[5, 6]),
+            false => (0, 2, &[1, 2, 3]),
+        };
+
+        // The rook must still be in its corner.
+        match board.get_piece(rook_column, row) {
+            Piece::ROOK(rook) => {
+                if rook.get_color() != self.color {
+                    return;
+                }
+            },
+            _ => return,
+        }
+
+        // Every position between king and rook must be empty.
+        for column in between {
+            if let Piece::EMPTY(_) = board.get_piece(*column, row) {
+                continue;
+            }
+
+            return;
+        }
+
+        report.include_step(
+            board,                          // board: &Board,
+            target_column as i8,            // target_column: i8,
+            row as i8,                      // target_row: i8,
+            false,                          // no_empty: bool,
+            true,                           // no_rival: bool,
+        );
+    }
+}
+
 
 impl PieceExt for King{
     fn new(
@@ -91,8 +150,22 @@ impl PieceExt for King{
             board,                          // board: &Board,
         );
 
-        if report.is_check() {
-            panic!("Somehow the king managed to enable check. This shouldn't be possible.");
+        // A king "checking" the other one only happens while testing a step that walks a king
+        // next to the rival king. The check is what makes that step invalid, so keep it.
+
+        // Castling never captures, so it doesn't matter when we're only searching for check.
+        if !search_checkmate {
+            self.include_castling_step(
+                &mut report,                // report: &mut Report,
+                board,                      // board: &Board,
+                true,                       // king_side: bool,
+            );
+
+            self.include_castling_step(
+                &mut report,                // report: &mut Report,
+                board,                      // board: &Board,
+                false,                      // king_side: bool,
+            );
         }
 
         report
@@ -111,7 +111,8 @@ impl PieceExt for Pawn{
 
         let front_step = row as i8 + step;
 
-        report.include_step(
+        // Returns true only if the position in front is empty.
+        let front_is_empty: bool = report.include_step(
             board,                                  // board: &Board, 
             col as i8,                              // target_column: i8, 
             front_step,                             // target_row: i8, 
@@ -119,8 +120,8 @@ impl PieceExt for Pawn{
             true,                                   // no_rival: bool,
         );
         
-        if self.is_first_move() {
-            // If the pawn never moved, it can take an extra step forward.
+        if front_is_empty && self.is_first_move() {
+            // If the pawn never moved, it can take an extra step forward. But it can't jump over a piece.
             report.include_step(
                 board,                              // board: &Board, 
                 col as i8,                          // target_column: i8, 
@@ -146,6 +147,23 @@ impl PieceExt for Pawn{
             false,                                  // no_rival: bool,
         );
 
+        // En passant: a rival pawn that just took two steps can be captured as if it took only one.
+        // The position it skipped is empty, so it's included like a step forward.
+        if let Some(index) = board.get_en_passant() {
+            let en_passant_col: i8 = (index % 8) as i8;
+            let en_passant_row: i8 = (index / 8) as i8;
+
+            if (en_passant_row == front_step) && ((en_passant_col - col as i8).abs() == 1) {
+                report.include_step(
+                    board,                          // board: &Board, 
+                    en_passant_col,                 // target_column: i8, 
+                    en_passant_row,                 // target_row: i8, 
+                    false,                          // no_empty: bool, 
+                    true,                           // no_rival: bool,
+                );
+            }
+        }
+
         report
     }
 }
@@ -0,0 +1,34 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::pieces::piece::Color;
+
+
+/// The piece a pawn becomes after reaching the last row of the board.
+/// Chosen by the player when calling move_to.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Promotion{
+    QUEEN,
+    ROOK,
+    BISHOP,
+    KNIGHT,
+}
+
+impl Promotion{
+    pub fn get_piece_number(&self, color: &Color) -> u8 {
+        let white_number: u8 = match self {
+            Promotion::ROOK   => 2,
+            Promotion::KNIGHT => 3,
+            Promotion::BISHOP => 4,
+            Promotion::QUEEN  => 5,
+        };
+
+        match color {
+            Color::WHITE => white_number,
+            // Black pieces are numbered 6 above white ones.
+            Color::BLACK => white_number + 6,
+            Color::EMPTY => panic!("Error in Promotion.get_piece_number. Color is Empty."),
+        }
+    }
+}
@@ -1,3 +1,9 @@
+use near_sdk::serde::{Deserialize, Serialize};
+
+
+/// Why a move was rejected.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
 pub enum ErrorResponse{
     // If movement starts in a position that has no piece.
     NoPiece,
@@ -7,4 +13,36 @@ pub enum ErrorResponse{
     InvalidMove,
     // If the game is already over.
     GameOver,
+    // If a pawn reaches the last row without a promotion, or a promotion is given for any other move.
+    InvalidPromotion,
+    // If the account calling isn't the one playing the current color.
+    NotYourTurn,
+    // If a position index isn't lower than 64, or a column or row isn't lower than 8.
+    OutOfRange,
+    // If the move would leave the player's own king under check.
+    SelfCheck,
+    // If the text isn't a move in the expected notation.
+    InvalidNotation,
+    // If the notation is fine, but no valid move matches it.
+    NoMatchingMove,
+    // If the clock of the player to move ran out. The game ends with it.
+    OutOfTime,
+}
+
+impl ErrorResponse{
+    pub fn get_message(&self) -> &str {
+        match self {
+            ErrorResponse::NoPiece => "No piece found in given position.",
+            ErrorResponse::RivalPiece => "A rival player owns this piece.",
+            ErrorResponse::InvalidMove => "Invalid move.",
+            ErrorResponse::GameOver => "Game is already over, check status or call reset.",
+            ErrorResponse::InvalidPromotion => "Invalid promotion. Only a pawn reaching the last row is promoted, to a queen, rook, bishop or knight.",
+            ErrorResponse::NotYourTurn => "Only the account playing the current color can move.",
+            ErrorResponse::OutOfRange => "Invalid position. Index must be lower than 64.",
+            ErrorResponse::SelfCheck => "Invalid move. It would leave your king under check.",
+            ErrorResponse::InvalidNotation => "Move must be written like \"e2e4\", with the promotion letter if any, like \"e7e8q\".",
+            ErrorResponse::NoMatchingMove => "No valid move matches the notation.",
+            ErrorResponse::OutOfTime => "Time ran out, the game is over.",
+        }
+    }
 }
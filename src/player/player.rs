@@ -62,6 +62,10 @@ impl Player{
             panic!("Error in player.is_step_valid. search_checkmate is enabled, we will not find anything here.");
         }
 
+        if step.current_piece.get_color() == Color::EMPTY {
+            return Result::Err(ErrorResponse::NoPiece);
+        }
+
         if self.color != step.current_piece.get_color() {
             return Result::Err(ErrorResponse::RivalPiece);
         }
@@ -112,6 +116,8 @@ impl Player{
         let mut check = false;
         let pieces: &Vec<Piece> = &self.pieces;
         let reports: &mut Vec<Report> = &mut self.latest_reports;
+        // Reports from a previous board state would allow steps that are no longer valid.
+        reports.clear();
 
         for piece in pieces{
             let report: Report = piece.get_movement_report(
@@ -164,5 +170,30 @@ impl Player{
 
         false
     }
-}
 
+    /// Every step this player can do without leaving their own king under check.
+    pub fn get_valid_steps(
+        &self,
+        board: &mut Board,
+    ) -> Vec<Step> {
+        if self.search_checkmate{
+            panic!("Error in player.get_valid_steps. search_checkmate is enabled, we will not find anything here.");
+        }
+
+        let mut valid_steps: Vec<Step> = Vec::new();
+
+        for report in &self.latest_reports {
+            for step in report.get_steps() {
+                // can_avoid_checkmate needs a mutable step, the one in the report stays untouched.
+                let mut candidate: Step = step.clone();
+                if candidate.can_avoid_checkmate(
+                    board,                          // board: &mut Board,
+                ) {
+                    valid_steps.push(step.clone());
+                }
+            }
+        }
+
+        valid_steps
+    }
+}
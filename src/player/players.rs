@@ -1,3 +1,5 @@
+use near_sdk::AccountId;
+
 use crate::{
     board::Board,
     pieces::{
@@ -25,11 +27,21 @@ pub struct Players{
     black: Player,
     white: Player,
     turn: Turn,
+    // Accounts playing each color. Only the one for the current color can move.
+    white_account: AccountId,
+    black_account: AccountId,
 }
 
 
 impl Players{
-    pub fn new(board: &Board, current_player: bool, turn: u8) -> Self{
+    pub fn new(
+        board: &Board,
+        current_player: bool,
+        turn: u8,
+        halfmove_clock: u8,
+        white_account: AccountId,
+        black_account: AccountId,
+    ) -> Self{
 
         // log(&format!("Creating Black Pieces"));
         let mut black: Player = Player::new(
@@ -59,23 +71,36 @@ impl Players{
         let turn: Turn = Turn::new(
             current_player,                 // current_player: bool,
             turn,                           // turn: u8,
+            halfmove_clock,                 // halfmove_clock: u8,
         );
 
         Players{
             black,
             white,
             turn,
+            white_account,
+            black_account,
         }
     }
     
 
-    pub fn move_to(&mut self, board: &mut Board, mut target: Step) -> Result<(), ErrorResponse> {
+    pub fn move_to(&mut self, board: &mut Board, mut target: Step, account_id: &AccountId) -> Result<(), ErrorResponse> {
 
-        if self.turn.is_checkmate(){
+        if self.turn.is_game_over(){
             return Result::Err(ErrorResponse::GameOver);
         }
 
         let player_color: Color = self.turn.get_current_player_color();
+
+        let current_account: &AccountId = match player_color{
+            Color::EMPTY => panic!("Error in Players.move_to. Own color is Empty."),
+            Color::BLACK => &self.black_account,
+            Color::WHITE => &self.white_account,
+        };
+
+        if current_account != account_id {
+            return Result::Err(ErrorResponse::NotYourTurn);
+        }
         
         let (current_player, other_player): (&mut Player, &mut Player) = match player_color{
             Color::EMPTY => panic!("Error in Players.move_to. Own color is Empty."),
@@ -112,11 +137,16 @@ impl Players{
             return Result::Err(ErrorResponse::InvalidMove);
         }
 
+        // A pawn reaching the last row must choose a promotion, and no other move can have one.
+        if target.is_promotion() != target.promotion.is_some() {
+            return Result::Err(ErrorResponse::InvalidPromotion);
+        }
+
         // If the function returns false, then current player will be under check after their move.
         if !target.can_avoid_checkmate(
             board,                          // board: &mut Board,
         ){
-            return Result::Err(ErrorResponse::InvalidMove);
+            return Result::Err(ErrorResponse::SelfCheck);
         };
 
         // If it reaches here, then the movement is valid and will not trigger a check own itself.
@@ -145,6 +175,12 @@ impl Players{
             false,                          // search_checkmate: bool,
         );
 
+        // Returns true if there's any movement remaining that doesn't leave the rival under check.
+        // Must be checked after every move. Without any, it's either checkmate or stalemate.
+        let rival_can_move: bool = other_player.can_avoid_checkmate(
+            board,                          // board: &mut Board,
+        );
+
         if current_player.is_check(){
             match player_color{
                 Color::BLACK => {
@@ -157,10 +193,7 @@ impl Players{
                 _ => panic!("Invalid color type for Players.move_to. Own color is Empty."),
             }
 
-            // Returns true if there's any movement remaining that could save itself from checkmate.
-            if !other_player.can_avoid_checkmate(
-                board,                      // board: &mut Board,
-            ) {
+            if !rival_can_move {
                 // This means that there's no move that the rival player can take for saving themselves from check status
                 // Checkmate
                 // return Result::Err(ErrorResponse::CheckMate);
@@ -170,13 +203,20 @@ impl Players{
             // self.turn.next_turn()?;
             // return Result::Err(ErrorResponse::RivalIsCheck);
             
+        } else if !rival_can_move {
+            // Rival is not under check, but can't move either.
+            self.turn.set_stalemate();
+        }
+
+        // Dead position, nobody can win anymore.
+        if !self.turn.is_game_over() && board.is_insufficient_material() {
+            self.turn.set_insufficient_material();
         }
         
         // Move was successful, so we store the step and
         // go to the next turn.
         self.turn.next_turn(
-            // step list is not implemented yet
-            // target,                         // next_step: Step,
+            &target,                        // step: &Step,
         )?;
 
 
@@ -1,4 +1,10 @@
 use crate::{
+    game::result::{
+        DrawReason,
+        GameResult,
+        WinReason,
+    },
+    movement::step::Step,
     pieces::{
         piece::{
             Color,
@@ -13,13 +19,11 @@ pub struct Turn{
     current_player: Color,
     // How many turns this game went. If it reaches 255, calls reset.
     value: u8,
-    // A list of each step taken since the start of the round.
-    // It's public so we don't have to copy when retrieving value. Just access directly.
-    // pub steps: Vec<Step>,
-    // will implement later
+    // How many turns since the last capture or pawn move. Draw when it reaches 150 (75 moves each).
+    halfmove_clock: u8,
     black_check: bool,
     white_check: bool,
-    checkmate: bool,
+    result: GameResult,
 }
 
 
@@ -27,6 +31,7 @@ impl Turn{
     pub fn new(
         current_player: bool,
         value: u8,
+        halfmove_clock: u8,
     ) -> Self {
         let current_player = match current_player{
             false => Color::WHITE,
@@ -36,10 +41,10 @@ impl Turn{
         Turn{
             current_player,
             value,
-            // steps: Vec::new(),
+            halfmove_clock,
             black_check: false,
             white_check: false,
-            checkmate: false,
+            result: GameResult::Running,
         }
     }
 
@@ -59,6 +64,10 @@ impl Turn{
         self.value.clone()
     }
 
+    pub fn get_halfmove_clock(&self) -> u8 {
+        self.halfmove_clock
+    }
+
     pub fn is_black_check(&self) -> bool {
         self.black_check.clone()
     }
@@ -68,7 +77,15 @@ impl Turn{
     }
 
     pub fn is_checkmate(&self) -> bool {
-        self.checkmate.clone()
+        matches!(self.result, GameResult::WhiteWins(WinReason::Checkmate) | GameResult::BlackWins(WinReason::Checkmate))
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.result.is_game_over()
+    }
+
+    pub fn get_result(&self) -> GameResult {
+        self.result
     }
 
     pub fn set_black_check(&mut self) {
@@ -87,11 +104,26 @@ impl Turn{
         self.white_check = false;
     }
 
+    /// Current player just put the rival in checkmate.
     pub fn set_checkmate(&mut self) {
-        self.checkmate = true;
+        self.result = match self.current_player {
+            Color::EMPTY => panic!("Error in Turn.set_checkmate. Own color is Empty."),
+            Color::WHITE => GameResult::WhiteWins(WinReason::Checkmate),
+            Color::BLACK => GameResult::BlackWins(WinReason::Checkmate),
+        };
     }
 
-    pub fn next_turn(&mut self) -> Result<(), ErrorResponse> {
+    /// Current player just left the rival without any valid move, but not under check.
+    pub fn set_stalemate(&mut self) {
+        self.result = GameResult::Draw(DrawReason::Stalemate);
+    }
+
+    /// Current player just left the board without enough pieces for anyone to checkmate.
+    pub fn set_insufficient_material(&mut self) {
+        self.result = GameResult::Draw(DrawReason::InsufficientMaterial);
+    }
+
+    pub fn next_turn(&mut self, step: &Step) -> Result<(), ErrorResponse> {
         let current_player: Color = self.current_player;
 
         self.current_player = match current_player{
@@ -101,11 +133,18 @@ impl Turn{
         };
 
         self.value += 1;
-        
-        // Won't store more than 128 steps, which is two or three times the average length of games.
-        // if self.turn <= 128{
-        //     self.steps.push(next_step);
-        // }
+
+        // Captures and pawn moves can't be undone, so they restart the count.
+        if step.is_capture() || step.is_pawn_move() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        // 75 moves each without progress ends the game, unless this move already did (checkmate).
+        if (self.halfmove_clock >= 150) && !self.is_game_over() {
+            self.result = GameResult::Draw(DrawReason::SeventyFiveMoveRule);
+        }
         
         // 255 turns is way over the top. 
         // Probably an AI that doesn't know what to do.
@@ -0,0 +1,75 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::game::result::GameResult;
+
+
+// Every account starts with this rating.
+const INITIAL_RATING: u32 = 1500;
+// Ratings change faster for new players, and slower for the strongest ones.
+const PROVISIONAL_GAMES: u32 = 30;
+const MASTER_RATING: u32 = 2400;
+
+
+/// Elo rating of an account, and how many rated games it finished.
+#[derive(Clone, Serialize, Deserialize, BorshDeserialize, BorshSerialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Rating{
+    rating: u32,
+    games: u32,
+}
+
+impl Default for Rating{
+    fn default() -> Self{
+        Rating{
+            rating: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+impl Rating{
+    pub fn get_rating(&self) -> u32 {
+        self.rating
+    }
+
+    pub fn get_games(&self) -> u32 {
+        self.games
+    }
+
+    /// 40 for the first 30 games, 10 from a rating of 2400, 20 otherwise.
+    pub fn get_k_factor(&self) -> u32 {
+        if self.games < PROVISIONAL_GAMES {
+            40
+        } else if self.rating >= MASTER_RATING {
+            10
+        } else {
+            20
+        }
+    }
+
+    /// Score expected against rival, from 0 (sure loss) to 1 (sure win).
+    pub fn get_expected_score(&self, rival: &Rating) -> f64 {
+        let difference: f64 = rival.rating as f64 - self.rating as f64;
+
+        1.0 / (1.0 + 10f64.powf(difference / 400.0))
+    }
+
+    /// Update after a game against rival (with the rival's rating before the game). score: 1 win, 0.5 draw, 0 loss.
+    pub fn update(&mut self, rival: &Rating, score: f64) {
+        let change: f64 = (self.get_k_factor() as f64) * (score - self.get_expected_score(rival));
+
+        self.rating = (self.rating as f64 + change).round().max(0.0) as u32;
+        self.games += 1;
+    }
+}
+
+/// Score of White in a finished game. None if it doesn't count for ratings.
+pub fn get_white_score(result: &GameResult) -> Option<f64> {
+    match result {
+        GameResult::WhiteWins(_) => Some(1.0),
+        GameResult::BlackWins(_) => Some(0.0),
+        GameResult::Draw(_) => Some(0.5),
+        GameResult::Running | GameResult::Aborted => None,
+    }
+}
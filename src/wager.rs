@@ -0,0 +1,89 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json;
+use near_sdk::{env, AccountId, Gas, Promise};
+
+use crate::challenge::ChallengeId;
+
+
+// NEP-141 requires exactly 1 yoctoNEAR attached to ft_transfer.
+const ONE_YOCTO: u128 = 1;
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+const GAS_FOR_ON_TOKEN_PAYOUT: Gas = 10_000_000_000_000;
+/// Least gas attached to Chess.claim_unpaid: the token transfer, its callback and the call itself.
+pub const GAS_FOR_CLAIM_UNPAID: Gas = GAS_FOR_FT_TRANSFER + GAS_FOR_ON_TOKEN_PAYOUT + 10_000_000_000_000;
+
+
+/// msg of an ft_transfer_call to the contract: which challenge the tokens are the wager for.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferMessage {
+    pub challenge_id: ChallengeId,
+}
+
+impl TransferMessage {
+    pub fn parse(msg: &str) -> Option<Self> {
+        serde_json::from_str(msg).ok()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct FtTransferArgs {
+    receiver_id: AccountId,
+    amount: U128,
+    memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct OnTokenPayoutArgs {
+    account_id: AccountId,
+    token_id: AccountId,
+    amount: U128,
+}
+
+fn to_json<T: Serialize>(args: &T) -> Vec<u8> {
+    match serde_json::to_vec(args) {
+        Result::Ok(json) => json,
+        Result::Err(err) => panic!("Error in wager.to_json. Couldn't serialize the arguments ({}).", err),
+    }
+}
+
+/// Send amount of the wager currency to account_id. token is the NEP-141 contract, or None for NEAR.
+/// A token transfer that fails is kept by Chess.on_token_payout, so it can be claimed again.
+/// Tokens are only sent from Chess.claim_unpaid, which checks there's gas for it.
+pub fn pay(account_id: &AccountId, token: &Option<AccountId>, amount: u128) {
+    if amount == 0 {
+        return;
+    }
+
+    match token {
+        None => {
+            Promise::new(account_id.clone()).transfer(amount);
+        },
+        Some(token_id) => {
+            Promise::new(token_id.clone())
+                .function_call(
+                    b"ft_transfer".to_vec(),                    // method_name: Vec<u8>,
+                    to_json(&FtTransferArgs{                    // arguments: Vec<u8>,
+                        receiver_id: account_id.clone(),
+                        amount: U128(amount),
+                        memo: Some(String::from("chess wager")),
+                    }),
+                    ONE_YOCTO,                                  // amount: Balance,
+                    GAS_FOR_FT_TRANSFER,                        // gas: Gas,
+                )
+                .then(Promise::new(env::current_account_id()).function_call(
+                    b"on_token_payout".to_vec(),                // method_name: Vec<u8>,
+                    to_json(&OnTokenPayoutArgs{                 // arguments: Vec<u8>,
+                        account_id: account_id.clone(),
+                        token_id: token_id.clone(),
+                        amount: U128(amount),
+                    }),
+                    0,                                          // amount: Balance,
+                    GAS_FOR_ON_TOKEN_PAYOUT,                    // gas: Gas,
+                ));
+        },
+    }
+}